@@ -0,0 +1,129 @@
+use std::str::FromStr;
+
+use crate::snake::{Penalty, PowerUpRates};
+
+// the adaptive speed changes by these factors, and stays within these bounds
+const ADAPTIVE_SLOW_DOWN: f64 = 0.85;
+const ADAPTIVE_SPEED_UP: f64 = 1.1;
+const ADAPTIVE_MIN: f64 = 0.5;
+const ADAPTIVE_MAX: f64 = 2.0;
+// the collisions close together that slow the game down, the moves without one after which they
+// are forgotten, and the moves without one that speed it up
+const ADAPTIVE_COLLISIONS: usize = 2;
+const ADAPTIVE_COLLISION_WINDOW: usize = 60;
+const ADAPTIVE_CLEAN_MOVES: usize = 300;
+
+/// A preset for the speed, the speed progression, the penalty and the power-up rates.
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+    /// Normal, but the speed follows how well the game goes.
+    Adaptive,
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Difficulty, String> {
+        match name {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            "insane" => Ok(Difficulty::Insane),
+            "adaptive" => Ok(Difficulty::Adaptive),
+            _ => Err(format!("unknown difficulty '{}'", name)),
+        }
+    }
+}
+
+impl Difficulty {
+    /// The starting speed in fps.
+    pub fn speed(self) -> f64 {
+        match self {
+            Difficulty::Easy => 20.0,
+            Difficulty::Normal | Difficulty::Adaptive => 30.0,
+            Difficulty::Hard => 40.0,
+            Difficulty::Insane => 60.0,
+        }
+    }
+
+    /// How much faster the snake gets, and after how much food.
+    pub fn speed_step(self) -> (f64, usize) {
+        match self {
+            Difficulty::Easy => (0.02, 100),
+            Difficulty::Normal | Difficulty::Adaptive => (0.05, 100),
+            Difficulty::Hard => (0.05, 50),
+            Difficulty::Insane => (0.1, 25),
+        }
+    }
+
+    pub fn penalty(self) -> Penalty {
+        match self {
+            // an easy collision keeps the snake where it was
+            Difficulty::Easy => Penalty::Shrink(3),
+            _ => Penalty::Reset,
+        }
+    }
+
+    pub fn power_ups(self) -> PowerUpRates {
+        let rates = PowerUpRates::default();
+        let scale = match self {
+            Difficulty::Easy => 2.0,
+            Difficulty::Normal | Difficulty::Adaptive => 1.0,
+            Difficulty::Hard => 0.5,
+            Difficulty::Insane => 0.0,
+        };
+        PowerUpRates {
+            bomb: rates.bomb * scale,
+            line: rates.line * scale,
+            magnet: rates.magnet * scale,
+        }
+    }
+}
+
+/// The speed factor of the adaptive difficulty, it slows down after repeated collisions and speeds
+/// up after a long clean streak.
+pub struct AdaptiveSpeed {
+    factor: f64,
+    collisions: usize,
+    clean_moves: usize,
+}
+
+impl AdaptiveSpeed {
+    pub fn new() -> AdaptiveSpeed {
+        AdaptiveSpeed {
+            factor: 1.0,
+            collisions: 0,
+            clean_moves: 0,
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    pub fn on_move(&mut self, collided: bool) {
+        if collided {
+            self.clean_moves = 0;
+            self.collisions += 1;
+            if self.collisions >= ADAPTIVE_COLLISIONS {
+                self.collisions = 0;
+                self.factor = (self.factor * ADAPTIVE_SLOW_DOWN).max(ADAPTIVE_MIN);
+            }
+        } else {
+            self.clean_moves += 1;
+            if self.clean_moves >= ADAPTIVE_COLLISION_WINDOW {
+                // a collision long ago doesn't add up with the next one
+                self.collisions = 0;
+            }
+            if self.clean_moves >= ADAPTIVE_CLEAN_MOVES {
+                self.clean_moves = 0;
+                self.factor = (self.factor * ADAPTIVE_SPEED_UP).min(ADAPTIVE_MAX);
+            }
+        }
+    }
+}
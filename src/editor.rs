@@ -0,0 +1,304 @@
+use crossterm::{
+    cursor,
+    event::{
+        read, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind,
+    },
+    terminal, ExecutableCommand, Result,
+};
+
+use crate::level::{Level, EMPTY, FOOD, WALL};
+use crate::renderer::{Renderer, Terminal};
+use crate::screen_buffer::{Coordinate, GameContent, ScreenBuffer, TextLayout};
+use crate::snake::PADDING;
+use crate::theme::Theme;
+
+const SPAWNS: [char; 4] = ['^', '>', 'v', '<'];
+const HELP: &str = "arrows/mouse move, space/click paint, right click erase, \
+                    w wall, f food, e erase, s spawn, 1-9 portal, t text, u undo, r redo, \
+                    ctrl+s save, ESC quit";
+
+/// Edits a level file in the terminal.
+///
+/// Every paint stroke, a key press or a mouse drag, is one step for undo and redo. The level is
+/// saved even if it isn't playable yet, the problems are shown in the status line instead.
+pub struct Editor {
+    level: Level,
+    path: String,
+    text: String,
+    cursor: Coordinate,
+    brush: char,
+    show_text: bool,
+    undo: Vec<Level>,
+    redo: Vec<Level>,
+    // the level before the current stroke, it goes on the undo stack once the stroke changes a cell
+    stroke: Option<Level>,
+    is_saved: bool,
+    is_quitting: bool,
+    status: String,
+    text_layout: TextLayout,
+    theme: Theme,
+}
+
+impl Editor {
+    pub fn new(
+        level: Level,
+        path: String,
+        text: String,
+        text_layout: TextLayout,
+        theme: Theme,
+    ) -> Editor {
+        Editor {
+            cursor: Coordinate {
+                row: level.height() / 2,
+                col: level.width() / 2,
+            },
+            level,
+            path,
+            text,
+            brush: WALL,
+            show_text: false,
+            undo: vec![],
+            redo: vec![],
+            stroke: None,
+            is_saved: true,
+            is_quitting: false,
+            status: String::new(),
+            text_layout,
+            theme,
+        }
+    }
+
+    pub fn run(&mut self) -> Result<()> {
+        let mut terminal = Terminal::new();
+        terminal.start()?;
+        terminal.stdout().execute(EnableMouseCapture)?;
+
+        loop {
+            self.draw(&mut terminal)?;
+            let is_done = match read()? {
+                Event::Key(event) => self.handle_key(event),
+                Event::Mouse(event) => {
+                    self.handle_mouse(event);
+                    false
+                }
+                Event::Resize(_, _) => {
+                    terminal
+                        .stdout()
+                        .execute(terminal::Clear(terminal::ClearType::All))?;
+                    false
+                }
+            };
+            if is_done {
+                break;
+            }
+        }
+
+        terminal
+            .stdout()
+            .execute(DisableMouseCapture)?
+            .execute(terminal::Clear(terminal::ClearType::All))?
+            .execute(cursor::MoveTo(0, 0))?;
+        terminal.finish()
+    }
+
+    /// Handles a key press and returns whether the editor should close.
+    fn handle_key(&mut self, event: KeyEvent) -> bool {
+        let is_ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        if event.code != KeyCode::Esc {
+            self.is_quitting = false;
+        }
+        match event.code {
+            KeyCode::Esc => {
+                if self.is_saved || self.is_quitting {
+                    return true;
+                }
+                self.is_quitting = true;
+                self.status = "unsaved changes, press ESC again to quit".to_string();
+            }
+            KeyCode::Up => self.move_cursor(-1, 0),
+            KeyCode::Down => self.move_cursor(1, 0),
+            KeyCode::Left => self.move_cursor(0, -1),
+            KeyCode::Right => self.move_cursor(0, 1),
+            KeyCode::Char('s') if is_ctrl => self.save(),
+            KeyCode::Char('z') if is_ctrl => self.undo(),
+            KeyCode::Char('y') if is_ctrl => self.redo(),
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                self.begin_stroke();
+                self.paint(self.cursor, self.brush);
+            }
+            KeyCode::Char('w') => self.brush = WALL,
+            KeyCode::Char('f') => self.brush = FOOD,
+            KeyCode::Char('e') => self.brush = EMPTY,
+            KeyCode::Char('s') => {
+                // pressing 's' again turns the spawn clockwise
+                self.brush = match SPAWNS.iter().position(|&spawn| spawn == self.brush) {
+                    Some(idx) => SPAWNS[(idx + 1) % SPAWNS.len()],
+                    None => SPAWNS[0],
+                }
+            }
+            KeyCode::Char(c @ '1'..='9') => self.brush = c,
+            KeyCode::Char('t') => self.show_text = !self.show_text,
+            KeyCode::Char('u') => self.undo(),
+            KeyCode::Char('r') => self.redo(),
+            _ => {}
+        }
+        false
+    }
+
+    fn handle_mouse(&mut self, event: MouseEvent) {
+        // each cell of the board is two terminal columns wide
+        let cell = Coordinate {
+            row: event.row as usize,
+            col: event.column as usize / 2,
+        };
+        if cell.row >= self.level.height() || cell.col >= self.level.width() {
+            return;
+        }
+        self.cursor = cell;
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                self.begin_stroke();
+                self.paint(cell, self.brush);
+            }
+            MouseEventKind::Down(MouseButton::Right) => {
+                self.begin_stroke();
+                self.paint(cell, EMPTY);
+            }
+            MouseEventKind::Drag(MouseButton::Left) => self.paint(cell, self.brush),
+            MouseEventKind::Drag(MouseButton::Right) => self.paint(cell, EMPTY),
+            _ => {}
+        }
+    }
+
+    fn move_cursor(&mut self, rows: isize, cols: isize) {
+        let row = self.cursor.row as isize + rows;
+        let col = self.cursor.col as isize + cols;
+        if row >= 0
+            && col >= 0
+            && (row as usize) < self.level.height()
+            && (col as usize) < self.level.width()
+        {
+            self.cursor = Coordinate {
+                row: row as usize,
+                col: col as usize,
+            };
+        }
+    }
+
+    fn begin_stroke(&mut self) {
+        self.stroke = Some(self.level.clone());
+    }
+
+    fn paint(&mut self, cell: Coordinate, c: char) {
+        let is_border = cell.row == 0
+            || cell.col == 0
+            || cell.row == self.level.height() - 1
+            || cell.col == self.level.width() - 1;
+        if is_border || self.level.get(cell.row, cell.col) == c {
+            return;
+        }
+        if let Some(level) = self.stroke.take() {
+            self.undo.push(level);
+            self.redo.clear();
+        }
+        // there is only one spawn, so painting a new one moves it
+        if SPAWNS.contains(&c) {
+            for row in 0..self.level.height() {
+                for col in 0..self.level.width() {
+                    if "S^>v<".contains(self.level.get(row, col)) {
+                        self.level.set(row, col, EMPTY);
+                    }
+                }
+            }
+        }
+        self.level.set(cell.row, cell.col, c);
+        self.is_saved = false;
+    }
+
+    fn undo(&mut self) {
+        if let Some(level) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.level, level));
+            self.is_saved = false;
+        }
+    }
+
+    fn redo(&mut self) {
+        if let Some(level) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.level, level));
+            self.is_saved = false;
+        }
+    }
+
+    fn save(&mut self) {
+        self.status = match self.level.save(&self.path) {
+            Ok(()) => {
+                self.is_saved = true;
+                let problem = self
+                    .level
+                    .validate()
+                    .and_then(|_| self.level.check_text(&self.text, PADDING, self.text_layout));
+                match problem {
+                    Ok(()) => format!("saved {}", self.path),
+                    Err(err) => format!("saved {}, but {}", self.path, err),
+                }
+            }
+            Err(err) => format!("can't save {}: {}", self.path, err),
+        };
+    }
+
+    fn screen(&self) -> ScreenBuffer {
+        let mut buffer =
+            ScreenBuffer::new(self.level.width(), self.level.height(), GameContent::Empty);
+        buffer.set_text_layout(self.text_layout);
+        for row in 0..self.level.height() {
+            for col in 0..self.level.width() {
+                let content = match self.level.get(row, col) {
+                    WALL => GameContent::Wall,
+                    FOOD if !self.show_text => GameContent::Food,
+                    '1'..='9' => GameContent::Portal,
+                    'S' | '^' | '>' | 'v' | '<' => GameContent::SnakeHead,
+                    _ => GameContent::Empty,
+                };
+                buffer.set_at(row, col, content);
+            }
+        }
+        if self.show_text && buffer.layout_text(&self.text, PADDING).is_ok() {
+            buffer.fill_with_text(self.text.clone(), PADDING);
+        }
+        let cursor = match self.level.get(self.cursor.row, self.cursor.col) {
+            EMPTY | FOOD => '+',
+            c => c,
+        };
+        buffer.set_at(
+            self.cursor.row,
+            self.cursor.col,
+            GameContent::SnakeBodyWithChar(cursor),
+        );
+        buffer
+    }
+
+    fn draw(&self, renderer: &mut dyn Renderer) -> Result<()> {
+        self.screen().draw(renderer, &self.theme)?;
+        let brush = match self.brush {
+            WALL => "wall".to_string(),
+            FOOD => "food".to_string(),
+            EMPTY => "erase".to_string(),
+            c @ '1'..='9' => format!("portal {}", c),
+            c => format!("spawn {}", c),
+        };
+        let status = format!(
+            "{} | brush: {} | row {}, column {}{} | {}",
+            self.path,
+            brush,
+            self.cursor.row + 1,
+            self.cursor.col + 1,
+            if self.is_saved { "" } else { " | modified" },
+            self.status
+        );
+        let row = self.level.height() as u16;
+        renderer.print_line(row, &status)?;
+        renderer.print_line(row + 1, HELP)?;
+        renderer.flush()
+    }
+}
@@ -0,0 +1,222 @@
+use std::ops::Range;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, io};
+
+use crossterm::style::Color;
+
+use crate::renderer::{AnsiFile, HtmlFile, SvgFile};
+use crate::screen_buffer::{GameContent, ScreenBuffer};
+use crate::theme::Theme;
+
+const HIDDEN_CHAR: char = '#';
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Plain,
+    Ansi,
+    Html,
+    Svg,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Plain => "txt",
+            Format::Ansi => "ans",
+            Format::Html => "html",
+            Format::Svg => "svg",
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Format, String> {
+        match name {
+            "plain" => Ok(Format::Plain),
+            "ansi" => Ok(Format::Ansi),
+            "html" => Ok(Format::Html),
+            "svg" => Ok(Format::Svg),
+            _ => Err(format!("unknown format '{}'", name)),
+        }
+    }
+}
+
+/// Renders the given rows of the buffer the way `ScreenBuffer::draw` puts them on the terminal.
+pub fn export(buffer: &ScreenBuffer, rows: Range<usize>, format: Format, theme: &Theme) -> String {
+    match format {
+        Format::Plain => to_plain(buffer, rows),
+        Format::Ansi => to_ansi(buffer, rows, theme),
+        Format::Html => to_html(buffer, rows, theme),
+        Format::Svg => to_svg(buffer, rows, theme),
+    }
+}
+
+/// Writes the whole buffer into a new file in the working directory and returns its name.
+pub fn save_screenshot(buffer: &ScreenBuffer, format: Format, theme: &Theme) -> io::Result<String> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default();
+    let path = format!("snake-{}.{}", timestamp, format.extension());
+    fs::write(&path, export(buffer, 0..buffer.height(), format, theme))?;
+    Ok(path)
+}
+
+/// The rows that contain anything but empty cells.
+pub fn used_rows(buffer: &ScreenBuffer) -> Range<usize> {
+    let is_used = |row: &usize| {
+        (0..buffer.width()).any(|col| !matches!(buffer.get_at(*row, col), GameContent::Empty))
+    };
+    let first = (0..buffer.height()).find(is_used).unwrap_or(0);
+    let last = (0..buffer.height()).rev().find(is_used).unwrap_or(0);
+    first..last + 1
+}
+
+fn plain_char(content: GameContent) -> char {
+    match content {
+        GameContent::Character(c)
+        | GameContent::SnakeHeadWithChar(c)
+        | GameContent::SnakeBodyWithChar(c)
+        | GameContent::BorderWithChar(c) => c,
+        GameContent::Empty => ' ',
+        _ => HIDDEN_CHAR,
+    }
+}
+
+fn to_plain(buffer: &ScreenBuffer, rows: Range<usize>) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let line: String = (0..buffer.width())
+            .map(|col| plain_char(buffer.get_at(row, col)))
+            .collect();
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+fn to_ansi(buffer: &ScreenBuffer, rows: Range<usize>, theme: &Theme) -> String {
+    let mut file = AnsiFile::new(vec![]);
+    crop(buffer, rows).draw(&mut file, theme).unwrap();
+    String::from_utf8(file.into_inner()).unwrap()
+}
+
+/// A copy of the given rows of the buffer.
+fn crop(buffer: &ScreenBuffer, rows: Range<usize>) -> ScreenBuffer {
+    let mut cropped = ScreenBuffer::new(buffer.width(), rows.len(), GameContent::Empty);
+    for (idx, row) in rows.enumerate() {
+        for col in 0..buffer.width() {
+            cropped.set_at(idx, col, buffer.get_at(row, col));
+        }
+    }
+    cropped
+}
+
+fn to_html(buffer: &ScreenBuffer, rows: Range<usize>, theme: &Theme) -> String {
+    let mut file = HtmlFile::new(vec![]);
+    crop(buffer, rows).draw(&mut file, theme).unwrap();
+    String::from_utf8(file.into_inner()).unwrap()
+}
+
+fn to_svg(buffer: &ScreenBuffer, rows: Range<usize>, theme: &Theme) -> String {
+    let mut file = SvgFile::new(vec![]);
+    crop(buffer, rows).draw(&mut file, theme).unwrap();
+    String::from_utf8(file.into_inner()).unwrap()
+}
+
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The RGB value of a terminal color, using the xterm defaults for the named colors.
+pub fn rgb(color: Color) -> (u8, u8, u8) {
+    match color {
+        Color::Reset | Color::Black => (0, 0, 0),
+        Color::DarkGrey => (127, 127, 127),
+        Color::Red => (255, 0, 0),
+        Color::DarkRed => (205, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::DarkGreen => (0, 205, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::DarkYellow => (205, 205, 0),
+        Color::Blue => (92, 92, 255),
+        Color::DarkBlue => (0, 0, 238),
+        Color::Magenta => (255, 0, 255),
+        Color::DarkMagenta => (205, 0, 205),
+        Color::Cyan => (0, 255, 255),
+        Color::DarkCyan => (0, 205, 205),
+        Color::White => (255, 255, 255),
+        Color::Grey => (229, 229, 229),
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(value) => ansi_value_rgb(value),
+    }
+}
+
+/// The 16 named colors in the order of their ANSI values.
+pub const NAMED_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+fn ansi_value_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => rgb(NAMED_COLORS[value as usize]),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+            let value = value - 16;
+            (level(value / 36), level(value / 6 % 6), level(value % 6))
+        }
+        _ => {
+            let gray = 8 + 10 * (value - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+pub fn css_color(color: Color) -> String {
+    let (r, g, b) = rgb(color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::assert_snapshot;
+    use crate::i18n::Message;
+    use crate::snake::{SnakeGame, TEXT};
+
+    #[test]
+    fn reveal_shows_only_the_message() {
+        let game = SnakeGame::new(
+            true,
+            ScreenBuffer::new(40, 40, GameContent::Empty),
+            Message::Single(TEXT.to_string()),
+        );
+        let board = game.board().unwrap();
+        let rows = used_rows(&board);
+        assert_snapshot(
+            "reveal_plain",
+            &export(&board, rows, Format::Plain, &Theme::classic()),
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// how many of the latest frames the statistics cover
+const WINDOW: usize = 120;
+
+/// The times between the latest frames that were drawn, for the FPS overlay.
+pub struct FrameStats {
+    frame_times: VecDeque<Duration>,
+    last_frame: Option<Instant>,
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            frame_times: VecDeque::with_capacity(WINDOW),
+            last_frame: None,
+        }
+    }
+
+    pub fn record(&mut self, now: Instant) {
+        if let Some(last_frame) = self.last_frame {
+            if self.frame_times.len() == WINDOW {
+                self.frame_times.pop_front();
+            }
+            self.frame_times
+                .push_back(now.saturating_duration_since(last_frame));
+        }
+        self.last_frame = Some(now);
+    }
+
+    /// The frame rate and the minimum, average and 99th percentile of the frame times.
+    pub fn summary(&self) -> String {
+        if self.frame_times.is_empty() {
+            return String::new();
+        }
+        let mut millis: Vec<f64> = self
+            .frame_times
+            .iter()
+            .map(|time| time.as_secs_f64() * 1000.0)
+            .collect();
+        millis.sort_by(|a, b| a.total_cmp(b));
+        let avg = millis.iter().sum::<f64>() / millis.len() as f64;
+        let p99 = millis[((millis.len() - 1) as f64 * 0.99).round() as usize];
+        format!(
+            "{:.1} fps  frame time min {:.1} avg {:.1} p99 {:.1} ms",
+            1000.0 / avg,
+            millis[0],
+            avg,
+            p99
+        )
+    }
+}
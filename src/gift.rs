@@ -0,0 +1,106 @@
+use std::{fs, io};
+
+use crate::difficulty::Difficulty;
+use crate::i18n::Message;
+use crate::level::Level;
+use crate::screen_buffer::{GameContent, ScreenBuffer, TextLayout};
+use crate::snake::{SnakeGame, PADDING};
+
+pub const GIFT_EXTENSION: &str = "snakegift";
+// the start screen and the initial snake need at least this much room
+const MIN_SCREEN_SIZE: usize = 30;
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Recipient {
+    pub name: String,
+    #[serde(default)]
+    pub from: Option<String>,
+}
+
+/// Everything the recipient needs to play: the hidden message and the board it is laid out on.
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct Gift {
+    pub recipient: Recipient,
+    pub message: Message,
+    /// The difficulty the sender picked, the recipient can still pick another one.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+    pub width: usize,
+    pub height: usize,
+    #[serde(default)]
+    pub map: Option<Level>,
+}
+
+impl Gift {
+    /// Reads the gift without checking it, the layout of its message depends on how it is played,
+    /// see `validate`.
+    pub fn read(path: &str) -> io::Result<Gift> {
+        let bytes = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(bytes.as_str())?)
+    }
+
+    pub fn save(&self, path: &str, text_layout: TextLayout) -> io::Result<()> {
+        self.validate(text_layout)?;
+        let bytes = serde_json::to_string_pretty(self)?;
+        fs::write(path, bytes)
+    }
+
+    /// Checks that the message fits the board the same way `fill_with_text` lays it out.
+    pub fn validate(&self, text_layout: TextLayout) -> io::Result<()> {
+        if self.width < MIN_SCREEN_SIZE || self.height < MIN_SCREEN_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "the board must be at least {0}x{0}, got {1}x{2}",
+                    MIN_SCREEN_SIZE, self.width, self.height
+                ),
+            ));
+        }
+        if let Some(map) = &self.map {
+            map.validate()?;
+            if map.width() != self.width || map.height() != self.height {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "the map is {}x{}, but the board is {}x{}",
+                        map.width(),
+                        map.height(),
+                        self.width,
+                        self.height
+                    ),
+                ));
+            }
+        }
+        let mut screen_buffer = self.screen_buffer();
+        screen_buffer.set_text_layout(text_layout);
+        for text in self.message.texts() {
+            if text.trim().is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the message is empty",
+                ));
+            }
+            screen_buffer.layout_text(text, PADDING)?;
+            if let Some(map) = &self.map {
+                map.check_text(text, PADDING, text_layout)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn new_game(&self, reveal: bool) -> SnakeGame {
+        let mut game = SnakeGame::new(reveal, self.screen_buffer(), self.message.clone());
+        game.set_recipient(self.recipient.name.clone(), self.recipient.from.clone());
+        if let Some(difficulty) = self.difficulty {
+            game.set_difficulty(difficulty);
+        }
+        if let Some(map) = &self.map {
+            game.set_level(map.clone());
+        }
+        game
+    }
+
+    fn screen_buffer(&self) -> ScreenBuffer {
+        ScreenBuffer::new(self.width, self.height, GameContent::Empty)
+    }
+}
@@ -0,0 +1,313 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use crate::renderer::TextGrid;
+use crate::screen_buffer::ScreenBuffer;
+use crate::snake::{Session, SnakeGame};
+use crate::theme::{Shapes, Theme};
+
+// the time that passes with every move, for the notices and the speed effects
+const MOVE_TIME: Duration = Duration::from_millis(33);
+
+/// Plays a game without a terminal, the moves come from a script instead of the keyboard.
+///
+/// Every move is one step of the game, no matter how fast it would be played, so the same script
+/// and seed always end on the same board.
+pub struct Headless {
+    game: SnakeGame,
+    session: Session,
+    now: Instant,
+    is_game_over: bool,
+}
+
+impl Headless {
+    pub fn new(mut game: SnakeGame) -> Headless {
+        let session = game.begin();
+        Headless {
+            game,
+            session,
+            now: Instant::now(),
+            is_game_over: false,
+        }
+    }
+
+    /// Plays every move of the script, see `play_move`.
+    pub fn play(&mut self, script: &str) -> Result<(), String> {
+        script.chars().try_for_each(|c| self.play_move(c))
+    }
+
+    /// `u`, `d`, `l` and `r` turn the snake like the arrow keys and move it, `.` moves it straight
+    /// on and whitespace is skipped. Once the game is over the moves don't do anything.
+    pub fn play_move(&mut self, c: char) -> Result<(), String> {
+        let key = match c.to_ascii_lowercase() {
+            'u' => Some(KeyCode::Up),
+            'd' => Some(KeyCode::Down),
+            'l' => Some(KeyCode::Left),
+            'r' => Some(KeyCode::Right),
+            '.' => None,
+            c if c.is_whitespace() => return Ok(()),
+            c => return Err(format!("unknown move '{}'", c)),
+        };
+        if let Some(code) = key {
+            self.session.press(KeyEvent::from(code));
+        }
+        self.step();
+        Ok(())
+    }
+
+    pub fn step(&mut self) {
+        if self.is_game_over {
+            return;
+        }
+        self.now += MOVE_TIME;
+        self.is_game_over = self.game.step(&mut self.session, self.now).is_some();
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.is_game_over
+    }
+
+    pub fn game(&self) -> &SnakeGame {
+        &self.game
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// The frame the way it would be drawn now, see `snapshot`.
+    pub fn snapshot(&mut self) -> String {
+        snapshot(self.game.frame(&mut self.session, self.now))
+    }
+}
+
+/// The buffer as text with one character per cell, the characters of `--ascii`.
+pub fn snapshot(buffer: &ScreenBuffer) -> String {
+    let theme = Theme {
+        shapes: Shapes::Ascii,
+        ..Theme::classic()
+    };
+    let mut grid = TextGrid::new();
+    buffer
+        .draw(&mut grid, &theme)
+        .expect("Can't draw into memory");
+    // a cell is two terminal columns wide, the first one has its character
+    grid.text()
+        .lines()
+        .map(|line| {
+            let cells: String = line.chars().step_by(2).collect();
+            format!("{}\n", cells.trim_end())
+        })
+        .collect()
+}
+
+/// Compares the snapshot with the golden file `snapshots/<name>.txt`.
+///
+/// Set `UPDATE_SNAPSHOTS=1` to write the files instead, after checking that the change is right.
+#[cfg(test)]
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = format!("{}/snapshots/{}.txt", env!("CARGO_MANIFEST_DIR"), name);
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::create_dir_all(format!("{}/snapshots", env!("CARGO_MANIFEST_DIR"))).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    match std::fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => {}
+        Ok(expected) => panic!("{}", snapshot_diff(name, &expected, actual)),
+        Err(err) => panic!(
+            "can't read {}: {}, run with UPDATE_SNAPSHOTS=1 to write it:\n{}",
+            path, err, actual
+        ),
+    }
+}
+
+/// Both versions of the rows that differ with the differing columns marked, and the other rows
+/// once for orientation.
+#[cfg(test)]
+fn snapshot_diff(name: &str, expected: &str, actual: &str) -> String {
+    use std::fmt::Write;
+
+    let mut out = format!(
+        "snapshot '{}' changed, run with UPDATE_SNAPSHOTS=1 if that is right\n",
+        name
+    );
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    for row in 0..expected.len().max(actual.len()) {
+        let old = expected.get(row).copied().unwrap_or("");
+        let new = actual.get(row).copied().unwrap_or("");
+        if old == new {
+            writeln!(out, "{:>4}   |{}|", row, old).unwrap();
+            continue;
+        }
+        let (old_chars, new_chars): (Vec<char>, Vec<char>) =
+            (old.chars().collect(), new.chars().collect());
+        let marks: String = (0..old_chars.len().max(new_chars.len()))
+            .map(|col| {
+                if old_chars.get(col) == new_chars.get(col) {
+                    ' '
+                } else {
+                    '^'
+                }
+            })
+            .collect();
+        writeln!(out, "{:>4} - |{}|", row, old).unwrap();
+        writeln!(out, "{:>4} + |{}|", row, new).unwrap();
+        writeln!(out, "        {}", marks.trim_end()).unwrap();
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Message;
+    use crate::level::Level;
+    use crate::screen_buffer::GameContent;
+    use crate::snake::{Direction, Penalty, PowerUpRates, Walls};
+    use proptest::prelude::*;
+
+    const TEXT: &str = "hi there";
+    const LONG_TEXT: &str =
+        "Happy birthday! I wish you a year full of sunny days and good friends.\nSee you soon";
+
+    /// A small board without power-ups, the snake starts at row 6, column 5 and moves up.
+    fn game(width: usize, height: usize) -> SnakeGame {
+        let mut game = SnakeGame::new(
+            false,
+            ScreenBuffer::new(width, height, GameContent::Empty),
+            Message::Single(TEXT.to_string()),
+        );
+        game.set_level(Level::new(width, height));
+        game.set_power_up_rates(PowerUpRates {
+            bomb: 0.0,
+            line: 0.0,
+            magnet: 0.0,
+        });
+        game.set_seed(7);
+        game
+    }
+
+    #[test]
+    fn start() {
+        let mut headless = Headless::new(game(22, 12));
+        assert_snapshot("headless_start", &headless.snapshot());
+    }
+
+    #[test]
+    fn eating_grows_the_snake() {
+        let mut headless = Headless::new(game(22, 12));
+        headless.play("uu rrrr d").unwrap();
+        assert_eq!(headless.session().snake().body_pos.len(), 3 + 7);
+        assert_snapshot("headless_eating", &headless.snapshot());
+    }
+
+    #[test]
+    fn collision_with_the_border_respawns() {
+        let mut headless = Headless::new(game(22, 12));
+        headless.play("uuuuuu").unwrap();
+        assert_snapshot("headless_border_respawn", &headless.snapshot());
+    }
+
+    #[test]
+    fn collision_with_the_body_shrinks() {
+        let mut game = game(22, 12);
+        game.set_penalty(Penalty::Shrink(2));
+        let mut headless = Headless::new(game);
+        headless.play("u rrrr d l u").unwrap();
+        assert!(!headless.is_game_over());
+        assert_snapshot("headless_body_shrink", &headless.snapshot());
+    }
+
+    #[test]
+    fn wrapping_walls() {
+        let mut game = game(22, 12);
+        game.set_walls(Walls::Wrap);
+        let mut headless = Headless::new(game);
+        headless.play("lllll").unwrap();
+        assert_snapshot("headless_wrap", &headless.snapshot());
+    }
+
+    #[test]
+    fn eating_reveals_the_message() {
+        // the snake runs into the border once it has eaten the row, so that it gets out of the way
+        let mut game = game(22, 12);
+        game.set_penalty(Penalty::Reset);
+        let mut headless = Headless::new(game);
+        headless.play("u rrrrrrrrrrr dddddd .").unwrap();
+        assert_snapshot("headless_revealed_text", &headless.snapshot());
+    }
+
+    #[test]
+    fn reveal_mode_shows_the_wrapped_message() {
+        let mut game = SnakeGame::new(
+            true,
+            ScreenBuffer::new(40, 24, GameContent::Empty),
+            Message::Single(LONG_TEXT.to_string()),
+        );
+        game.set_seed(7);
+        let mut headless = Headless::new(game);
+        headless.play("uu").unwrap();
+        assert_snapshot("headless_reveal_wrapped", &headless.snapshot());
+    }
+
+    #[test]
+    fn two_turns_between_moves_take_two_moves() {
+        let mut headless = Headless::new(game(22, 12));
+        headless.session.press(KeyEvent::from(KeyCode::Left));
+        headless.session.press(KeyEvent::from(KeyCode::Down));
+        headless.step();
+        assert_eq!(headless.session().snake().direction, Direction::LEFT);
+        headless.step();
+        assert_eq!(headless.session().snake().direction, Direction::DOWN);
+        assert_eq!(headless.game().score(), 2);
+    }
+
+    #[test]
+    fn unknown_moves_are_rejected() {
+        let mut headless = Headless::new(game(22, 12));
+        assert_eq!(headless.play("ux"), Err("unknown move 'x'".to_string()));
+    }
+
+    proptest! {
+        #[test]
+        fn the_snake_grows_only_by_eating(
+            script in "[udlr.]{1,80}",
+            wrap in any::<bool>(),
+            seed in any::<u64>(),
+        ) {
+            let mut game = game(14, 10);
+            game.set_walls(if wrap { Walls::Wrap } else { Walls::Solid });
+            game.set_penalty(Penalty::Shrink(0));
+            game.set_special_food_chance(0.0);
+            game.set_seed(seed);
+            let mut headless = Headless::new(game);
+            for c in script.chars() {
+                let length = headless.session().snake().body_pos.len();
+                let score = headless.game().score();
+                headless.play_move(c).unwrap();
+                let body = &headless.session().snake().body_pos;
+                for cell in body {
+                    prop_assert!(0 < cell.row && cell.row < 9 && 0 < cell.col && cell.col < 13);
+                }
+                if headless.game().score() == score {
+                    prop_assert_eq!(body.len(), length);
+                } else {
+                    prop_assert_eq!(body.len(), length + 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn diff_marks_the_changed_cells() {
+        let diff = snapshot_diff("board", "+--+\n|..|\n", "+--+\n|.@|\n");
+        assert_eq!(
+            diff,
+            "snapshot 'board' changed, run with UPDATE_SNAPSHOTS=1 if that is right\n   \
+             0   |+--+|\n   1 - ||..||\n   1 + ||.@||\n          ^\n"
+        );
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::env;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// The user interface strings shown around the board.
+pub struct UiStrings {
+    pub title: &'static str,
+    pub esc_to_stop: &'static str,
+    pub controls: &'static str,
+    pub starting_in: &'static str,
+    pub score: &'static str,
+    pub revealed: &'static str,
+    pub message_complete: &'static str,
+    pub press_any_key: &'static str,
+    pub lives: &'static str,
+    pub game_over: &'static str,
+    pub length: &'static str,
+    pub time: &'static str,
+    pub restart: &'static str,
+    pub continue_forgiving: &'static str,
+    pub quit: &'static str,
+    pub high_score: &'static str,
+    pub golden_food: &'static str,
+    pub poison: &'static str,
+    pub faster: &'static str,
+    pub slower: &'static str,
+    pub bomb: &'static str,
+    pub line: &'static str,
+    pub magnet: &'static str,
+    pub gift_for: &'static str,
+    pub gift_from: &'static str,
+}
+
+const EN: UiStrings = UiStrings {
+    title: "SNAKE",
+    esc_to_stop: "ESC to stop",
+    controls: "~ CONTROLS IT by ARROWS ~",
+    starting_in: "Starting in",
+    score: "Score:",
+    revealed: "revealed",
+    message_complete: "MESSAGE COMPLETE",
+    press_any_key: "press any key to exit",
+    lives: "Lives:",
+    game_over: "GAME OVER",
+    length: "Length:",
+    time: "Time:",
+    restart: "R to restart",
+    continue_forgiving: "C to continue without dying",
+    quit: "ESC to quit",
+    high_score: "Best:",
+    golden_food: "golden food",
+    poison: "poison",
+    faster: "faster",
+    slower: "slower",
+    bomb: "bomb",
+    line: "line",
+    magnet: "magnet",
+    gift_for: "for",
+    gift_from: "from",
+};
+
+const DE: UiStrings = UiStrings {
+    title: "SNAKE",
+    esc_to_stop: "ESC zum Beenden",
+    controls: "~ STEUERUNG MIT PFEILEN ~",
+    starting_in: "Start in",
+    score: "Punkte:",
+    revealed: "aufgedeckt",
+    message_complete: "NACHRICHT KOMPLETT",
+    press_any_key: "beliebige Taste zum Beenden",
+    lives: "Leben:",
+    game_over: "GAME OVER",
+    length: "Länge:",
+    time: "Zeit:",
+    restart: "R für Neustart",
+    continue_forgiving: "C zum Weiterspielen ohne Tod",
+    quit: "ESC zum Beenden",
+    high_score: "Rekord:",
+    golden_food: "goldenes Futter",
+    poison: "Gift",
+    faster: "schneller",
+    slower: "langsamer",
+    bomb: "Bombe",
+    line: "Linie",
+    magnet: "Magnet",
+    gift_for: "für",
+    gift_from: "von",
+};
+
+const UK: UiStrings = UiStrings {
+    title: "ЗМІЙКА",
+    esc_to_stop: "ESC для виходу",
+    controls: "~ КЕРУВАННЯ СТРІЛКАМИ ~",
+    starting_in: "Початок через",
+    score: "Рахунок:",
+    revealed: "відкрито",
+    message_complete: "ПОВІДОМЛЕННЯ ВІДКРИТО",
+    press_any_key: "натисніть будь-яку клавішу",
+    lives: "Життя:",
+    game_over: "КІНЕЦЬ ГРИ",
+    length: "Довжина:",
+    time: "Час:",
+    restart: "R щоб почати знову",
+    continue_forgiving: "C грати далі без смертей",
+    quit: "ESC для виходу",
+    high_score: "Рекорд:",
+    golden_food: "золота їжа",
+    poison: "отрута",
+    faster: "швидше",
+    slower: "повільніше",
+    bomb: "бомба",
+    line: "лінія",
+    magnet: "магніт",
+    gift_for: "для",
+    gift_from: "від",
+};
+
+const HE: UiStrings = UiStrings {
+    title: "נחש",
+    esc_to_stop: "לחץ ESC ליציאה",
+    controls: "~ שליטה בחיצים ~",
+    starting_in: "מתחיל בעוד",
+    score: "ניקוד:",
+    revealed: "נחשף",
+    message_complete: "ההודעה נחשפה",
+    press_any_key: "לחץ על מקש כלשהו ליציאה",
+    lives: "חיים:",
+    game_over: "המשחק נגמר",
+    length: "אורך:",
+    time: "זמן:",
+    restart: "לחץ R להתחלה מחדש",
+    continue_forgiving: "לחץ C להמשך בלי למות",
+    quit: "לחץ ESC ליציאה",
+    high_score: "שיא:",
+    golden_food: "אוכל זהב",
+    poison: "רעל",
+    faster: "מהר יותר",
+    slower: "לאט יותר",
+    bomb: "פצצה",
+    line: "קו",
+    magnet: "מגנט",
+    gift_for: "עבור",
+    gift_from: "מאת",
+};
+
+/// Returns the language part of a locale, e.g. `de` for `de_DE.UTF-8`.
+pub fn language(locale: &str) -> &str {
+    locale.split(['_', '-', '.', '@']).next().unwrap_or(locale)
+}
+
+pub fn ui_strings(locale: &str) -> &'static UiStrings {
+    match language(locale) {
+        "de" => &DE,
+        "uk" => &UK,
+        "he" => &HE,
+        _ => &EN,
+    }
+}
+
+/// Picks the locale from the explicit choice or the usual environment variables.
+pub fn detect_locale(explicit: Option<&str>) -> String {
+    let from_env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty());
+    let locale = explicit.map(str::to_string).or(from_env);
+    match locale {
+        Some(locale) if locale != "C" && locale != "POSIX" => {
+            locale.split('.').next().unwrap_or(&locale).to_string()
+        }
+        _ => DEFAULT_LOCALE.to_string(),
+    }
+}
+
+/// A hidden message, either a single text or one text per locale.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum Message {
+    Single(String),
+    Bundle(BTreeMap<String, String>),
+}
+
+impl Message {
+    /// Picks the text for `locale`, falling back to its language, to English and then to any
+    /// text in the bundle.
+    pub fn select(&self, locale: &str) -> &str {
+        match self {
+            Message::Single(text) => text,
+            Message::Bundle(texts) => texts
+                .get(locale)
+                .or_else(|| texts.get(language(locale)))
+                .or_else(|| texts.get(DEFAULT_LOCALE))
+                .or_else(|| texts.values().next())
+                .map(String::as_str)
+                .unwrap_or(""),
+        }
+    }
+
+    pub fn texts(&self) -> Vec<&str> {
+        match self {
+            Message::Single(text) => vec![text.as_str()],
+            Message::Bundle(texts) => texts.values().map(String::as_str).collect(),
+        }
+    }
+}
+
+fn is_rtl(c: char) -> bool {
+    matches!(c as u32,
+        0x0590..=0x08FF // Hebrew, Arabic, Syriac, Thaana, NKo
+        | 0xFB1D..=0xFDFF // Hebrew and Arabic presentation forms A
+        | 0xFE70..=0xFEFF) // Arabic presentation forms B
+}
+
+/// Reorders a single line of text into the order it has to be drawn from left to right.
+///
+/// This is a small subset of the Unicode bidi algorithm: the direction of the line is taken from
+/// its first strong character, and runs of Latin letters and digits inside a right-to-left line
+/// keep their own order. Spaces and punctuation between two such characters belong to their run,
+/// so that `שלום hello world!` keeps `hello world` together.
+pub fn visual_order(line: &str) -> String {
+    let first_strong = line.chars().find(|c| c.is_alphabetic());
+    if !first_strong.is_some_and(is_rtl) {
+        return line.to_string();
+    }
+
+    let chars: Vec<char> = line.chars().collect();
+    let is_ltr = |c: char| c.is_ascii_alphanumeric() || (c.is_alphabetic() && !is_rtl(c));
+    let mut runs: Vec<(bool, String)> = vec![];
+    for (i, &c) in chars.iter().enumerate() {
+        let is_ltr = is_ltr(c)
+            || (!c.is_alphanumeric()
+                && chars[..i]
+                    .iter()
+                    .rev()
+                    .find(|c| c.is_alphanumeric())
+                    .is_some_and(|&c| is_ltr(c))
+                && chars[i + 1..]
+                    .iter()
+                    .find(|c| c.is_alphanumeric())
+                    .is_some_and(|&c| is_ltr(c)));
+        match runs.last_mut() {
+            Some((run_is_ltr, run)) if *run_is_ltr == is_ltr => run.push(c),
+            _ => runs.push((is_ltr, c.to_string())),
+        }
+    }
+    runs.iter()
+        .rev()
+        .map(|(is_ltr, run)| {
+            if *is_ltr {
+                run.clone()
+            } else {
+                run.chars().rev().collect()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_to_right_runs_keep_their_spaces() {
+        assert_eq!(visual_order("שלום hello world!"), "!hello world םולש");
+        assert_eq!(visual_order("hello world"), "hello world");
+    }
+}
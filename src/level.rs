@@ -0,0 +1,348 @@
+use std::collections::VecDeque;
+use std::{fs, io};
+
+use crate::screen_buffer::{Coordinate, GameContent, ScreenBuffer, TextLayout};
+use crate::snake::{Direction, Snake};
+
+pub const WALL: char = '#';
+pub const FOOD: char = '.';
+pub const EMPTY: char = ' ';
+pub const SPAWN: char = '^';
+const SPAWNS: [(char, Direction); 5] = [
+    ('S', Direction::UP),
+    ('^', Direction::UP),
+    ('>', Direction::RIGHT),
+    ('v', Direction::DOWN),
+    ('<', Direction::LEFT),
+];
+
+/// A board layout, stored as a grid of characters covering the whole board.
+///
+/// `#` is a wall, `.` is food, a space is an empty cell, and one of `S^>v<` marks where the head
+/// of the snake starts and which way it moves (`S` moves up). The two cells marked with the same
+/// digit from `1` to `9` are linked portals. When the grid has no `.` at all, every cell that is
+/// neither a wall nor a portal is food. The outermost ring is always the border.
+///
+/// A level file is either the grid as plain text or JSON like `{"name": "...", "grid": [...]}`.
+#[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct Level {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub grid: Vec<String>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Level {
+    /// An empty level with walls around it and the spawn on the left side.
+    pub fn new(width: usize, height: usize) -> Level {
+        let grid = (0..height)
+            .map(|row| {
+                (0..width)
+                    .map(|col| {
+                        if row == 0 || col == 0 || row == height - 1 || col == width - 1 {
+                            WALL
+                        } else {
+                            EMPTY
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut level = Level { name: None, grid };
+        // inside the walls even on the smallest level
+        level.set(height / 2, (width / 4).max(1), SPAWN);
+        level
+    }
+
+    pub fn load(path: &str) -> io::Result<Level> {
+        let level = Level::read(path)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    /// Reads a level file without checking that it can be played, e.g. to edit it.
+    pub fn read(path: &str) -> io::Result<Level> {
+        let bytes = fs::read_to_string(path)?;
+        if path.ends_with(".json") {
+            Ok(serde_json::from_str(bytes.as_str())?)
+        } else {
+            Ok(Level {
+                name: None,
+                grid: bytes.lines().map(str::to_string).collect(),
+            })
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let bytes = if path.ends_with(".json") {
+            serde_json::to_string_pretty(self)?
+        } else {
+            self.grid.join("\n") + "\n"
+        };
+        fs::write(path, bytes)
+    }
+
+    pub fn width(&self) -> usize {
+        self.grid.first().map_or(0, |row| row.chars().count())
+    }
+
+    pub fn height(&self) -> usize {
+        self.grid.len()
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> char {
+        self.grid
+            .get(row)
+            .and_then(|line| line.chars().nth(col))
+            .unwrap_or(EMPTY)
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, c: char) {
+        if let Some(line) = self.grid.get_mut(row) {
+            *line = line
+                .chars()
+                .enumerate()
+                .map(|(i, old)| if i == col { c } else { old })
+                .collect();
+        }
+    }
+
+    fn is_wall(&self, row: usize, col: usize) -> bool {
+        self.get(row, col) == WALL
+    }
+
+    fn is_inside(&self, row: usize, col: usize) -> bool {
+        row > 0 && col > 0 && row < self.height() - 1 && col < self.width() - 1
+    }
+
+    fn has_food_regions(&self) -> bool {
+        self.grid.iter().any(|row| row.contains(FOOD))
+    }
+
+    fn is_food(&self, row: usize, col: usize) -> bool {
+        if self.has_food_regions() {
+            self.get(row, col) == FOOD
+        } else {
+            !self.is_wall(row, col)
+                && self.spawn_at(row, col).is_none()
+                && self.portal_at(row, col).is_none()
+        }
+    }
+
+    fn portal_at(&self, row: usize, col: usize) -> Option<char> {
+        Some(self.get(row, col)).filter(|c| ('1'..='9').contains(c))
+    }
+
+    fn cells(&self) -> impl Iterator<Item = Coordinate> + '_ {
+        (0..self.height())
+            .flat_map(move |row| (0..self.width()).map(move |col| Coordinate { row, col }))
+    }
+
+    /// The cell the snake comes out of when its head enters the portal at `entry`.
+    pub fn portal_exit(&self, entry: Coordinate) -> Option<Coordinate> {
+        let portal = self.portal_at(entry.row, entry.col)?;
+        self.cells()
+            .find(|&cell| cell != entry && self.portal_at(cell.row, cell.col) == Some(portal))
+    }
+
+    /// Draws the portals on the empty cells, e.g. after the snake passed them.
+    pub fn draw_portals(&self, buffer: &mut ScreenBuffer) {
+        for cell in self.cells() {
+            if self.portal_at(cell.row, cell.col).is_some()
+                && matches!(buffer.get_at(cell.row, cell.col), GameContent::Empty)
+            {
+                buffer.set_at(cell.row, cell.col, GameContent::Portal);
+            }
+        }
+    }
+
+    fn spawn_at(&self, row: usize, col: usize) -> Option<Direction> {
+        let c = self.get(row, col);
+        SPAWNS
+            .iter()
+            .find(|(spawn, _)| *spawn == c)
+            .map(|(_, direction)| *direction)
+    }
+
+    /// The snake the level starts with, its tail pointing away from the direction it moves.
+    pub fn spawn(&self) -> Option<Snake> {
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if let Some(direction) = self.spawn_at(row, col) {
+                    return Some(Snake::new_at(Coordinate { row, col }, direction));
+                }
+            }
+        }
+        None
+    }
+
+    /// Puts the walls and the food of the level on the board.
+    pub fn apply(&self, buffer: &mut ScreenBuffer, with_food: bool) {
+        for row in 0..self.height().min(buffer.height()) {
+            for col in 0..self.width().min(buffer.width()) {
+                let content = if self.is_wall(row, col) {
+                    GameContent::Wall
+                } else if self.portal_at(row, col).is_some() {
+                    GameContent::Portal
+                } else if with_food && self.is_food(row, col) {
+                    GameContent::Food
+                } else {
+                    GameContent::Empty
+                };
+                buffer.set_at(row, col, content);
+            }
+        }
+    }
+
+    /// Checks that the level is a rectangle of at least 3x3 cells, which is all the editor needs.
+    pub fn validate_shape(&self) -> io::Result<()> {
+        let width = self.width();
+        if width < 3 || self.height() < 3 {
+            return Err(invalid("the level is smaller than 3x3".to_string()));
+        }
+        if let Some(row) = self
+            .grid
+            .iter()
+            .position(|row| row.chars().count() != width)
+        {
+            return Err(invalid(format!(
+                "row {} of the level isn't {} cells wide",
+                row + 1,
+                width
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that the grid is a rectangle with a valid spawn, and that no food is sealed off.
+    pub fn validate(&self) -> io::Result<()> {
+        self.validate_shape()?;
+        let width = self.width();
+
+        let snake = self
+            .spawn()
+            .ok_or_else(|| invalid("the level has no spawn point".to_string()))?;
+        let spawns = (0..self.height())
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.spawn_at(row, col).is_some())
+            .count();
+        if spawns > 1 {
+            return Err(invalid(format!("the level has {} spawn points", spawns)));
+        }
+        for coordinate in &snake.body_pos {
+            if !self.is_inside(coordinate.row, coordinate.col)
+                || self.is_wall(coordinate.row, coordinate.col)
+            {
+                return Err(invalid(format!(
+                    "the snake at the spawn point doesn't fit at row {}, column {}",
+                    coordinate.row + 1,
+                    coordinate.col + 1
+                )));
+            }
+        }
+
+        for portal in '1'..='9' {
+            let cells: Vec<Coordinate> = self
+                .cells()
+                .filter(|cell| self.portal_at(cell.row, cell.col) == Some(portal))
+                .collect();
+            if !cells.is_empty() && cells.len() != 2 {
+                return Err(invalid(format!(
+                    "the portal {} has {} ends instead of 2",
+                    portal,
+                    cells.len()
+                )));
+            }
+            if let Some(cell) = cells
+                .iter()
+                .find(|cell| !self.is_inside(cell.row, cell.col))
+            {
+                return Err(invalid(format!(
+                    "the portal at row {}, column {} is on the border",
+                    cell.row + 1,
+                    cell.col + 1
+                )));
+            }
+        }
+
+        let reachable = self.reachable_from(snake.body_pos[0]);
+        for row in 0..self.height() {
+            for col in 0..width {
+                if self.is_inside(row, col)
+                    && self.is_food(row, col)
+                    && !reachable[row * width + col]
+                {
+                    return Err(invalid(format!(
+                        "the food at row {}, column {} can't be reached",
+                        row + 1,
+                        col + 1
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that no wall or portal hides a character of the text laid out with `padding`.
+    pub fn check_text(
+        &self,
+        text: &str,
+        padding: usize,
+        text_layout: TextLayout,
+    ) -> io::Result<()> {
+        let mut buffer = ScreenBuffer::new(self.width(), self.height(), GameContent::Empty);
+        buffer.set_text_layout(text_layout);
+        buffer.layout_text(text, padding)?;
+        buffer.fill_with_text(text.to_string(), padding);
+        for row in 0..self.height() {
+            for col in 0..self.width() {
+                if let GameContent::Character(c) = buffer.get_at(row, col) {
+                    let is_covered = self.is_wall(row, col) || self.portal_at(row, col).is_some();
+                    if !c.is_whitespace() && is_covered {
+                        return Err(invalid(format!(
+                            "the cell at row {}, column {} hides the text",
+                            row + 1,
+                            col + 1
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Marks every cell inside the border that can be reached from `start` without crossing a wall,
+    /// going through portals.
+    pub fn reachable_from(&self, start: Coordinate) -> Vec<bool> {
+        let width = self.width();
+        let mut reachable = vec![false; width * self.height()];
+        let mut queue = VecDeque::new();
+        reachable[start.row * width + start.col] = true;
+        queue.push_back(start);
+        while let Some(cell) = queue.pop_front() {
+            let Coordinate { row, col } = cell;
+            let mut neighbours = vec![
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ];
+            if let Some(exit) = self.portal_exit(cell) {
+                neighbours.push((exit.row, exit.col));
+            }
+            for &(row, col) in neighbours.iter() {
+                if self.is_inside(row, col)
+                    && !self.is_wall(row, col)
+                    && !reachable[row * width + col]
+                {
+                    reachable[row * width + col] = true;
+                    queue.push_back(Coordinate { row, col });
+                }
+            }
+        }
+        reachable
+    }
+}
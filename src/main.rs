@@ -1,17 +1,43 @@
 extern crate clap;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use std::collections::BTreeMap;
+use std::io::Write;
 use std::{fs, io};
 
+mod difficulty;
+mod editor;
 mod events;
+mod export;
+mod frame_stats;
+mod gift;
+mod headless;
+mod i18n;
+mod level;
+mod map_gen;
+mod renderer;
 mod screen_buffer;
 mod snake;
+mod theme;
 
-use crate::screen_buffer::{GameContent, ScreenBuffer};
+use crate::difficulty::Difficulty;
+use crate::editor::Editor;
+use crate::export::Format;
+use crate::gift::{Gift, Recipient, GIFT_EXTENSION};
+use crate::headless::Headless;
+use crate::i18n::Message;
+use crate::level::Level;
+use crate::map_gen::MapGen;
+use crate::renderer::{Recording, Terminal};
+use crate::screen_buffer::{GameContent, ScreenBuffer, TextLayout};
+use crate::theme::{ColorSupport, Shapes, Theme};
 use crossterm::Result;
-use snake::SnakeGame;
+use snake::{Mode, Penalty, PowerUpRates, RunOptions, SnakeGame, Walls, PADDING, TEXT};
 
 const STATE_FILE: &str = "state.dump";
+const LEVEL_FILE: &str = "level.txt";
+const BOARD_SIZE: usize = 40;
+const HIGH_SCORE_FILE: &str = "highscore.dump";
 
 fn main() -> Result<()> {
     let matches = App::new("snake")
@@ -19,8 +45,10 @@ fn main() -> Result<()> {
         .author("Author: Green")
         .about("Almost a classic snake game for your terminal. You need to eat black squares, \
         and you will reveal something. The game saves the state on exit and loads on start(if you \
-        didn't set the '--new' flag). You don't need to be afraid of dying because you can't die=) \
-        The game will continue but with a shorter snake. If the default speed is to hight or low for \
+        didn't set the '--new' flag). By default you can't die: a collision throws the snake away and a \
+        new one starts somewhere else, '--penalty shrink' or '--penalty halve' keep a shorter snake \
+        going instead. '--mode survival' gives you '--lives' to lose, and '--mode classic' is the \
+        classic snake where the first collision ends the game. If the default speed is to hight or low for \
         you, then you can change is with the '--speed {number of fps}' flag. If you are tired of \
         the game and want to see the final result, you can specify the '--reveal' flag.")
         .arg(
@@ -28,56 +56,949 @@ fn main() -> Result<()> {
                 .short("r")
                 .long("reveal")
                 .help("reveal the message without game")
-                .takes_value(false),
+                .takes_value(false)
+                .global(true),
         )
         .arg(
             Arg::with_name("new")
                 .short("n")
                 .long("new")
                 .help("starts a new game")
-                .takes_value(false),
+                .takes_value(false)
+                .global(true),
         )
         .arg(
             Arg::with_name("speed")
                 .short("s")
                 .long("speed")
-                .help("the speed of the game in fps")
+                .help("the speed of the game in fps [default: from the difficulty, 30 for normal]")
                 .takes_value(true)
-                .default_value("30"),
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("render-fps")
+                .long("render-fps")
+                .help("how often the board is drawn per second, the speed of the snake doesn't depend on it")
+                .takes_value(true)
+                .default_value("60")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("show-fps")
+                .long("show-fps")
+                .help("shows the frame rate and the frame times below the board")
+                .takes_value(false)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("half-block")
+                .long("half-block")
+                .help("draws two rows of the board into one row of the terminal, new boards are twice as large")
+                .takes_value(false)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .long("theme")
+                .help("the colors of the board: 'classic', 'dark', 'solarized', 'high-contrast', 'colorblind', 'colorblind-dark' or a theme from the themes file")
+                .takes_value(true)
+                .default_value("classic")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("align")
+                .long("align")
+                .help("where the rows of the message sit on the board")
+                .takes_value(true)
+                .possible_values(&["center", "left", "justify"])
+                .default_value("center")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("hyphenate")
+                .long("hyphenate")
+                .help("puts a hyphen where a word that is too long for a row is broken up")
+                .takes_value(false)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("keep-spaces")
+                .long("keep-spaces")
+                .help("keeps runs of spaces in the message instead of a single space between the words")
+                .takes_value(false)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("line-spacing")
+                .long("line-spacing")
+                .help("the board rows between two rows of the message [default: 2 with '--half-block', which needs at least 2, else 1]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("glyphs")
+                .long("glyphs")
+                .help("draws the snake, the food and the walls as their own glyphs, so they can be told apart without colors")
+                .takes_value(false)
+                .conflicts_with("half-block")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("ascii")
+                .long("ascii")
+                .help("draws the board with plain ASCII characters and no colors, the default when $NO_COLOR is set or TERM=dumb")
+                .takes_value(false)
+                .conflicts_with("half-block")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("theme-file")
+                .long("theme-file")
+                .help("a JSON file with your own themes [default: themes.json if it exists]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("colors")
+                .long("colors")
+                .help("how many colors the terminal shows, 'auto' detects it from $COLORTERM and $TERM")
+                .takes_value(true)
+                .default_value("auto")
+                .possible_values(&["auto", "truecolor", "256", "16"])
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .help("writes the text of every frame that changed into the file, with the time it was shown")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("cell-aspect")
+                .long("cell-aspect")
+                .help("the height of a board cell divided by its width of two terminal columns, so that the snake moves as fast up and down as sideways")
+                .takes_value(true)
+                .default_value("1")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("difficulty")
+                .long("difficulty")
+                .help("presets the speed, how fast it grows, the penalty and the power-ups, 'adaptive' follows how well you play [default: normal]")
+                .takes_value(true)
+                .possible_values(&["easy", "normal", "hard", "insane", "adaptive"])
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("mode")
+                .long("mode")
+                .help("'forgiving' continues after collisions, 'survival' costs a life for each, 'classic' plays the classic snake with a high score [default: forgiving]")
+                .takes_value(true)
+                .possible_values(&["forgiving", "survival", "classic"])
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("lives")
+                .long("lives")
+                .help("the number of lives in the survival mode")
+                .takes_value(true)
+                .default_value("3")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("penalty")
+                .long("penalty")
+                .help("what a collision costs: 'reset' starts over with a new snake, 'shrink' and 'halve' keep a shorter snake nearby [default: reset]")
+                .takes_value(true)
+                .possible_values(&["reset", "shrink", "halve"])
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("shrink-by")
+                .long("shrink-by")
+                .help("the number of segments lost with '--penalty shrink'")
+                .takes_value(true)
+                .default_value("5")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("bomb-rate")
+                .long("bomb-rate")
+                .help("the chance per move that a bomb shows up, it eats the food around the head [default: 0.003]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("line-rate")
+                .long("line-rate")
+                .help("the chance per move that a line shows up, it eats the food in the row or column [default: 0.002]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("magnet-rate")
+                .long("magnet-rate")
+                .help("the chance per move that a magnet shows up, it eats the food next to the head for a while [default: 0.002]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("walls")
+                .long("walls")
+                .help("'solid' borders are walls, with 'wrap' the snake comes back on the other side [default: solid]")
+                .takes_value(true)
+                .possible_values(&["solid", "wrap"])
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("map")
+                .long("map")
+                .help("plays a new game on the walls and food of a level file")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("map-gen")
+                .long("map-gen")
+                .help("plays a new game on walls generated with the given style")
+                .takes_value(true)
+                .possible_values(&["maze", "caves", "rooms"])
+                .conflicts_with("map")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .long("seed")
+                .help("the seed of '--map-gen' and of the food, the same seed always generates the same walls and food [default: random]")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("slot")
+                .long("slot")
+                .help("the save slot to load and save the game")
+                .takes_value(true)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("print-message")
+                .long("print-message")
+                .help("prints the whole message to stdout once it is revealed")
+                .takes_value(false)
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("screenshot-format")
+                .long("screenshot-format")
+                .help("the format of the screenshots taken with 'p' while playing")
+                .takes_value(true)
+                .possible_values(&["ansi", "plain", "html", "svg"])
+                .default_value("ansi")
+                .global(true),
+        )
+        .arg(
+            Arg::with_name("locale")
+                .short("l")
+                .long("locale")
+                .help("the language of the game, e.g. 'de' or 'uk_UA' [default: $LANG]")
+                .takes_value(true)
+                .global(true),
+        )
+        .subcommand(
+            SubCommand::with_name("pack")
+                .about("packs a message into a gift file that can be played with 'play'")
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .help("the name of the recipient")
+                        .takes_value(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .help("the name of the sender")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("message")
+                        .short("m")
+                        .long("message")
+                        .help("the hidden message")
+                        .takes_value(true)
+                        .required_unless("message-file")
+                        .conflicts_with("message-file"),
+                )
+                .arg(
+                    Arg::with_name("message-file")
+                        .long("message-file")
+                        .help("reads the hidden message from a file")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::with_name("translation")
+                        .long("translation")
+                        .help("adds the message in another language from a file, e.g. 'de=message.de.txt'")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .long("width")
+                        .help("the width of the board, unless '--map' sets it")
+                        .takes_value(true)
+                        .default_value("40"),
+                )
+                .arg(
+                    Arg::with_name("height")
+                        .long("height")
+                        .help("the height of the board, unless '--map' sets it")
+                        .takes_value(true)
+                        .default_value("40"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("the gift file to write")
+                        .takes_value(true)
+                        .default_value("gift.snakegift"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("play")
+                .about("plays a gift file created with 'pack'")
+                .arg(
+                    Arg::with_name("gift")
+                        .help("the gift file to play")
+                        .required(true)
+                        .index(1),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("reveal")
+                .about("prints the message without starting the game")
+                .arg(
+                    Arg::with_name("gift")
+                        .help("the gift file to reveal instead of the built-in message")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .help("the output format")
+                        .takes_value(true)
+                        .possible_values(&["ansi", "plain", "html"])
+                        .default_value("ansi"),
+                )
+                .arg(
+                    Arg::with_name("plain")
+                        .long("plain")
+                        .help("the same as '--format plain'")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::with_name("from-save")
+                        .long("from-save")
+                        .help("shows only what is revealed in the save slot so far")
+                        .takes_value(false)
+                        .conflicts_with("gift"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("the file to write instead of stdout")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("export")
+                .about("exports the board of a save slot the way it is shown while playing")
+                .arg(
+                    Arg::with_name("format")
+                        .short("f")
+                        .long("format")
+                        .help("the output format")
+                        .takes_value(true)
+                        .possible_values(&["ansi", "plain", "html", "svg"])
+                        .default_value("ansi"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("the file to write instead of stdout")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("simulate")
+                .about("plays a script of moves without a terminal and prints the board with one character per cell")
+                .arg(
+                    Arg::with_name("moves")
+                        .help("'u', 'd', 'l' and 'r' turn the snake and move it, '.' moves it straight on")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("every-move")
+                        .long("every-move")
+                        .help("prints the board after every move instead of only at the end")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("edit")
+                .about("edits a level file for '--map' in the terminal")
+                .arg(
+                    Arg::with_name("level")
+                        .help("the level file to edit, it is created if it doesn't exist [default: level.txt]")
+                        .index(1),
+                )
+                .arg(
+                    Arg::with_name("width")
+                        .long("width")
+                        .help("the width of a new level")
+                        .takes_value(true)
+                        .default_value("40"),
+                )
+                .arg(
+                    Arg::with_name("height")
+                        .long("height")
+                        .help("the height of a new level")
+                        .takes_value(true)
+                        .default_value("40"),
+                )
+                .arg(
+                    Arg::with_name("message-file")
+                        .long("message-file")
+                        .help("the message to preview with 't' instead of the built-in one")
+                        .takes_value(true),
+                ),
         )
         .get_matches();
 
-    let reveal = matches.is_present("reveal");
-    let target_fps: f64 = matches
-        .value_of("speed")
-        .expect("Missed value for speed")
+    match matches.subcommand() {
+        ("pack", Some(pack_matches)) => pack(pack_matches),
+        ("reveal", Some(reveal_matches)) => reveal(reveal_matches),
+        ("export", Some(export_matches)) => {
+            let mut game = load_state(export_matches.value_of("slot"))?;
+            game.set_locale(i18n::detect_locale(export_matches.value_of("locale")));
+            game.set_text_layout(game_text_layout(export_matches, &game)?);
+            let screen = game.screen()?;
+            write_output(
+                export::export(
+                    &screen,
+                    0..screen.height(),
+                    parse_format(export_matches),
+                    &load_theme(export_matches)?,
+                ),
+                export_matches,
+            )
+        }
+        ("edit", Some(edit_matches)) => edit(edit_matches),
+        ("simulate", Some(simulate_matches)) => simulate(simulate_matches),
+        ("play", Some(play_matches)) => {
+            let path = play_matches.value_of("gift").expect("Missed the gift file");
+            let gift = load_gift(path, play_matches)?;
+            let reveal = play_matches.is_present("reveal");
+            let game = if play_matches.is_present("new") || is_classic(play_matches) {
+                gift.new_game(reveal)
+            } else {
+                load_state(play_matches.value_of("slot"))
+                    .ok()
+                    .filter(|game| *game.message() == gift.message)
+                    .unwrap_or_else(|| gift.new_game(reveal))
+            };
+            play(game, play_matches)
+        }
+        _ => {
+            let reveal = matches.is_present("reveal");
+            let level = load_builtin_level(&matches)?;
+            let game = if matches.is_present("new") || level.is_some() || is_classic(&matches) {
+                new_game(reveal, level, board_size(&matches))
+            } else {
+                load_state(matches.value_of("slot"))
+                    .unwrap_or_else(|_| new_game(reveal, None, board_size(&matches)))
+            };
+            play(game, &matches)
+        }
+    }
+}
+
+/// Applies the settings of the command line that the game remembers.
+fn configure(game: &mut SnakeGame, matches: &ArgMatches) -> io::Result<()> {
+    game.set_locale(i18n::detect_locale(matches.value_of("locale")));
+    game.set_text_layout(game_text_layout(matches, game)?);
+    if let Some(mode) = matches.value_of("mode") {
+        let mode: Mode = mode.parse().expect("Can't parse the mode value");
+        let lives = matches
+            .value_of("lives")
+            .expect("Missed value for lives")
+            .parse()
+            .expect("Can't parse the lives value");
+        game.set_mode(mode, lives);
+    }
+    if let Some(difficulty) = matches.value_of("difficulty") {
+        game.set_difficulty(
+            difficulty
+                .parse()
+                .expect("Can't parse the difficulty value"),
+        );
+    }
+    if let Some(walls) = matches.value_of("walls") {
+        let walls: Walls = walls.parse().expect("Can't parse the walls value");
+        game.set_walls(walls);
+    }
+    if let Some(penalty) = matches.value_of("penalty") {
+        let shrink_by = matches
+            .value_of("shrink-by")
+            .expect("Missed value for shrink-by")
+            .parse()
+            .expect("Can't parse the shrink-by value");
+        game.set_penalty(
+            Penalty::parse(penalty, shrink_by).expect("Can't parse the penalty value"),
+        );
+    }
+    let rate = |name: &str, current: f64| {
+        matches.value_of(name).map_or(current, |rate| {
+            rate.parse().expect("Can't parse the power-up rate value")
+        })
+    };
+    let rates = game.power_up_rates();
+    game.set_power_up_rates(PowerUpRates {
+        bomb: rate("bomb-rate", rates.bomb),
+        line: rate("line-rate", rates.line),
+        magnet: rate("magnet-rate", rates.magnet),
+    });
+    if let Some(seed) = matches.value_of("seed") {
+        game.set_seed(seed.parse().expect("Can't parse the seed value"));
+    }
+    Ok(())
+}
+
+fn play(mut game: SnakeGame, matches: &ArgMatches) -> Result<()> {
+    configure(&mut game, matches)?;
+    let options = RunOptions {
+        target_fps: matches
+            .value_of("speed")
+            .map_or(game.difficulty().speed(), |speed| {
+                speed.parse().expect("Can't parse the speed value")
+            }),
+        cell_aspect: matches
+            .value_of("cell-aspect")
+            .expect("Missed value for cell aspect")
+            .parse()
+            .expect("Can't parse the cell aspect value"),
+        render_fps: matches
+            .value_of("render-fps")
+            .expect("Missed value for render fps")
+            .parse()
+            .expect("Can't parse the render fps value"),
+        show_fps: matches.is_present("show-fps"),
+        half_block: is_half_block(matches),
+        screenshot_format: matches
+            .value_of("screenshot-format")
+            .expect("Missed value for screenshot format")
+            .parse()
+            .expect("Can't parse the screenshot format value"),
+        theme: load_theme(matches)?,
+    };
+
+    if is_classic(matches) {
+        game.set_high_score(load_high_score());
+    }
+
+    let terminal = Terminal::new();
+    match matches.value_of("record") {
+        Some(path) => {
+            let file = io::BufWriter::new(fs::File::create(path)?);
+            game.run(&mut Recording::new(terminal, file), &options)?
+        }
+        None => game.run(&mut { terminal }, &options)?,
+    }
+    if matches.is_present("print-message") && game.is_complete() {
+        println!("{}", game.text());
+    }
+    // a classic game doesn't replace the game with the message in the save slot
+    let saved = if is_classic(matches) {
+        fs::write(HIGH_SCORE_FILE, game.high_score().to_string())
+    } else {
+        let bytes = serde_json::to_string(&game).expect("Can't decode the state");
+        fs::write(state_file(matches.value_of("slot")), bytes)
+    };
+    if let Err(err) = saved {
+        println!("\n Can't save the state {}", err);
+    }
+
+    Ok(())
+}
+
+/// A classic game always starts on an empty board, it isn't loaded from a save slot.
+fn is_classic(matches: &ArgMatches) -> bool {
+    matches.value_of("mode") == Some("classic")
+}
+
+fn load_high_score() -> usize {
+    fs::read_to_string(HIGH_SCORE_FILE)
+        .ok()
+        .and_then(|high_score| high_score.trim().parse().ok())
+        .unwrap_or_default()
+}
+
+fn pack(matches: &ArgMatches) -> Result<()> {
+    let text = match matches.value_of("message-file") {
+        Some(path) => fs::read_to_string(path)?,
+        None => matches
+            .value_of("message")
+            .expect("Missed the message")
+            .to_string(),
+    };
+    let message = match matches.values_of("translation") {
+        Some(translations) => {
+            let mut texts = BTreeMap::new();
+            texts.insert(i18n::DEFAULT_LOCALE.to_string(), text);
+            for translation in translations {
+                let mut parts = translation.splitn(2, '=');
+                let locale = parts.next().unwrap_or_default();
+                let path = parts
+                    .next()
+                    .expect("The translation must look like 'locale=file'");
+                texts.insert(locale.to_string(), fs::read_to_string(path)?);
+            }
+            Message::Bundle(texts)
+        }
+        None => Message::Single(text),
+    };
+    let width = matches
+        .value_of("width")
+        .expect("Missed value for width")
+        .parse()
+        .expect("Can't parse the width value");
+    let height = matches
+        .value_of("height")
+        .expect("Missed value for height")
         .parse()
-        .expect("Can't parse the speed value");
+        .expect("Can't parse the height value");
+    let map = load_level(matches, width, height, &message.texts())?;
+    let gift = Gift {
+        recipient: Recipient {
+            name: matches
+                .value_of("to")
+                .expect("Missed the recipient")
+                .to_string(),
+            from: matches.value_of("from").map(str::to_string),
+        },
+        message,
+        difficulty: matches.value_of("difficulty").map(|difficulty| {
+            difficulty
+                .parse::<Difficulty>()
+                .expect("Can't parse the difficulty value")
+        }),
+        width: map.as_ref().map_or(width, Level::width),
+        height: map.as_ref().map_or(height, Level::height),
+        map,
+    };
+
+    let mut output = matches
+        .value_of("output")
+        .expect("Missed the output file")
+        .to_string();
+    if !output.ends_with(GIFT_EXTENSION) {
+        output = format!("{}.{}", output, GIFT_EXTENSION);
+    }
+    let texts = gift.message.texts();
+    gift.save(
+        &output,
+        text_layout(matches, gift.width, gift.height, &texts)?,
+    )?;
+    println!("Packed a gift for {} into {}", gift.recipient.name, output);
+    Ok(())
+}
 
-    let mut game = if matches.is_present("new") {
-        new_game(reveal)
+fn reveal(matches: &ArgMatches) -> Result<()> {
+    let format = if matches.is_present("plain") {
+        Format::Plain
     } else {
-        load_state().unwrap_or_else(|_| new_game(reveal))
+        parse_format(matches)
     };
+    let locale = i18n::detect_locale(matches.value_of("locale"));
 
-    game.run(target_fps)?;
-    let bytes = serde_json::to_string(&game).expect("Can't decode the state");
-    if let Err(err) = fs::write(STATE_FILE, bytes) {
-        println!("\n Can't save the state {}", err);
+    let from_save = matches.is_present("from-save");
+    let mut game = if from_save {
+        load_state(matches.value_of("slot"))?
+    } else {
+        match matches.value_of("gift") {
+            Some(path) => load_gift(path, matches)?.new_game(true),
+            None => new_game(true, load_builtin_level(matches)?, board_size(matches)),
+        }
+    };
+    game.set_locale(locale);
+    game.set_text_layout(game_text_layout(matches, &game)?);
+    let board = game.board()?;
+    let rows = if from_save {
+        0..board.height()
+    } else {
+        export::used_rows(&board)
+    };
+
+    write_output(
+        export::export(&board, rows, format, &load_theme(matches)?),
+        matches,
+    )
+}
+
+/// Plays the moves on a new game without a terminal, the game is the same every time unless
+/// `--seed` says otherwise.
+fn simulate(matches: &ArgMatches) -> Result<()> {
+    let mut game = new_game(false, load_builtin_level(matches)?, board_size(matches));
+    game.set_seed(0);
+    configure(&mut game, matches)?;
+    let mut headless = Headless::new(game);
+    let moves = matches.value_of("moves").expect("Missed the moves");
+    if matches.is_present("every-move") {
+        for (idx, c) in moves.chars().filter(|c| !c.is_whitespace()).enumerate() {
+            headless.play_move(c).expect("Can't parse the moves");
+            let length = headless.session().snake().body_pos.len();
+            let score = headless.game().score();
+            println!(
+                "--- move {} '{}', length {}, score {}",
+                idx + 1,
+                c,
+                length,
+                score
+            );
+            print!("{}", headless.snapshot());
+        }
+    } else {
+        headless.play(moves).expect("Can't parse the moves");
+        print!("{}", headless.snapshot());
     }
+    if headless.is_game_over() {
+        let locale = i18n::detect_locale(matches.value_of("locale"));
+        println!("{}", i18n::ui_strings(&locale).game_over);
+    }
+    Ok(())
+}
+
+fn edit(matches: &ArgMatches) -> Result<()> {
+    let path = matches.value_of("level").unwrap_or(LEVEL_FILE);
+    let level = if fs::metadata(path).is_ok() {
+        let level = Level::read(path)?;
+        // the level doesn't have to be playable yet, but the editor needs a board to paint on
+        level.validate_shape()?;
+        level
+    } else {
+        let width = matches
+            .value_of("width")
+            .expect("Missed value for width")
+            .parse()
+            .expect("Can't parse the width value");
+        let height = matches
+            .value_of("height")
+            .expect("Missed value for height")
+            .parse()
+            .expect("Can't parse the height value");
+        if width < 3 || height < 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a new level needs at least 3x3 cells",
+            )
+            .into());
+        }
+        Level::new(width, height)
+    };
+    let text = match matches.value_of("message-file") {
+        Some(path) => fs::read_to_string(path)?,
+        None => TEXT.to_string(),
+    };
+    let text_layout = text_layout(matches, level.width(), level.height(), &[&text])?;
+    Editor::new(
+        level,
+        path.to_string(),
+        text,
+        text_layout,
+        load_theme(matches)?,
+    )
+    .run()
+}
+
+/// The theme from `--theme`, `--glyphs` and `--ascii`, with the colors the terminal can show.
+fn load_theme(matches: &ArgMatches) -> Result<Theme> {
+    let support = match matches.value_of("colors").expect("Missed value for colors") {
+        "auto" => ColorSupport::detect(),
+        colors => colors.parse().expect("Can't parse the colors value"),
+    };
+    let mut theme = Theme::load(
+        matches.value_of("theme").expect("Missed value for theme"),
+        matches.value_of("theme-file"),
+    )?;
+    if is_ascii(matches) {
+        theme.shapes = Shapes::Ascii;
+    } else if matches.is_present("glyphs") {
+        theme.shapes = Shapes::Glyphs;
+    }
+    Ok(theme.adapted(support))
+}
 
+fn parse_format(matches: &ArgMatches) -> Format {
+    matches
+        .value_of("format")
+        .expect("Missed value for format")
+        .parse()
+        .expect("Can't parse the format value")
+}
+
+fn write_output(output: String, matches: &ArgMatches) -> Result<()> {
+    match matches.value_of("output") {
+        Some(path) => fs::write(path, output)?,
+        None => io::stdout().write_all(output.as_bytes())?,
+    }
     Ok(())
 }
 
-fn load_state() -> io::Result<SnakeGame> {
-    let bytes = fs::read_to_string(STATE_FILE)?;
+fn state_file(slot: Option<&str>) -> String {
+    match slot {
+        Some(slot) => format!("state.{}.dump", slot),
+        None => STATE_FILE.to_string(),
+    }
+}
+
+fn load_state(slot: Option<&str>) -> io::Result<SnakeGame> {
+    let bytes = fs::read_to_string(state_file(slot))?;
     let game = serde_json::from_str(bytes.as_str())?;
     Ok(game)
 }
 
-fn new_game(reveal: bool) -> SnakeGame {
-    let screen_height = 40;
-    let screen_width = 40;
+/// Loads the level given with '--map' or '--map-gen' for the built-in message.
+fn load_builtin_level(matches: &ArgMatches) -> io::Result<Option<Level>> {
+    let size = board_size(matches);
+    let level = load_level(matches, size, size, &[TEXT])?;
+    if let Some(level) = &level {
+        let text_layout = text_layout(matches, level.width(), level.height(), &[TEXT])?;
+        level.check_text(TEXT, PADDING, text_layout)?;
+    }
+    Ok(level)
+}
+
+/// Loads the level file given with '--map', or generates a level of the given size that keeps the
+/// `texts` readable.
+fn load_level(
+    matches: &ArgMatches,
+    width: usize,
+    height: usize,
+    texts: &[&str],
+) -> io::Result<Option<Level>> {
+    if let Some(path) = matches.value_of("map") {
+        return Ok(Some(Level::load(path)?));
+    }
+    match matches.value_of("map-gen") {
+        Some(kind) => {
+            let kind: MapGen = kind.parse().expect("Can't parse the map-gen value");
+            let seed = matches.value_of("seed").map_or_else(rand::random, |seed| {
+                seed.parse().expect("Can't parse the seed value")
+            });
+            let text_layout = text_layout(matches, width, height, texts)?;
+            let level = map_gen::generate(kind, width, height, seed, texts, text_layout);
+            level.validate()?;
+            Ok(Some(level))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Whether the game is drawn with plain ASCII, on request or because the terminal can't do more.
+fn is_ascii(matches: &ArgMatches) -> bool {
+    matches.is_present("ascii") || theme::is_dumb_terminal()
+}
+
+/// Half blocks can't be drawn with ASCII, so a dumb terminal gets the usual cells instead.
+fn is_half_block(matches: &ArgMatches) -> bool {
+    matches.is_present("half-block") && !is_ascii(matches)
+}
+
+/// The layout of the message from the command line for a board of the given size.
+///
+/// Half blocks only show one character per terminal row, so every row of the message needs a
+/// terminal row of its own: the line spacing is 2 unless `--line-spacing` asks for more, and the
+/// `texts` have to fit that way.
+fn text_layout(
+    matches: &ArgMatches,
+    width: usize,
+    height: usize,
+    texts: &[&str],
+) -> io::Result<TextLayout> {
+    let half_block = is_half_block(matches);
+    let text_layout = TextLayout {
+        align: matches
+            .value_of("align")
+            .expect("Missed value for align")
+            .parse()
+            .expect("Can't parse the align value"),
+        hyphenate: matches.is_present("hyphenate"),
+        keep_spaces: matches.is_present("keep-spaces"),
+        line_spacing: matches.value_of("line-spacing").map_or(
+            if half_block { 2 } else { 1 },
+            |line_spacing| {
+                line_spacing
+                    .parse()
+                    .expect("Can't parse the line spacing value")
+            },
+        ),
+    };
+    if !half_block {
+        return Ok(text_layout);
+    }
+    if text_layout.line_spacing < 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--half-block needs a line spacing of at least 2",
+        ));
+    }
+    let mut buffer = ScreenBuffer::new(width, height, GameContent::Empty);
+    buffer.set_text_layout(text_layout);
+    for text in texts {
+        buffer
+            .layout_text(text, PADDING)
+            .map_err(|err| io::Error::new(err.kind(), format!("with --half-block {}", err)))?;
+    }
+    Ok(text_layout)
+}
+
+fn game_text_layout(matches: &ArgMatches, game: &SnakeGame) -> io::Result<TextLayout> {
+    let (width, height) = game.size();
+    text_layout(matches, width, height, &game.message().texts())
+}
+
+/// Reads the gift and checks it with the layout it is going to be drawn with.
+fn load_gift(path: &str, matches: &ArgMatches) -> io::Result<Gift> {
+    let gift = Gift::read(path)?;
+    let texts = gift.message.texts();
+    gift.validate(text_layout(matches, gift.width, gift.height, &texts)?)?;
+    Ok(gift)
+}
+
+/// The size of a new board, twice as large when two rows fit into one terminal row.
+fn board_size(matches: &ArgMatches) -> usize {
+    if is_half_block(matches) {
+        2 * BOARD_SIZE
+    } else {
+        BOARD_SIZE
+    }
+}
+
+fn new_game(reveal: bool, level: Option<Level>, size: usize) -> SnakeGame {
+    let (screen_width, screen_height) = level
+        .as_ref()
+        .map_or((size, size), |level| (level.width(), level.height()));
     let screen_buffer = ScreenBuffer::new(screen_width, screen_height, GameContent::Empty);
-    SnakeGame::new(reveal, screen_buffer)
+    let mut game = SnakeGame::new(reveal, screen_buffer, Message::Single(TEXT.to_string()));
+    if let Some(level) = level {
+        game.set_level(level);
+    }
+    game
 }
@@ -0,0 +1,308 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::level::{Level, EMPTY, WALL};
+use crate::screen_buffer::{Coordinate, GameContent, ScreenBuffer, TextLayout};
+use crate::snake::PADDING;
+
+// the snake starts in the bottom left corner and moves right
+const SPAWN: char = '>';
+const SPAWN_COL: usize = 3;
+// the cells in front of the spawn that are kept free, so the snake doesn't crash right away
+const RUNWAY: usize = 3;
+const CAVE_FILL: f64 = 0.45;
+const CAVE_STEPS: usize = 4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MapGen {
+    Maze,
+    Caves,
+    Rooms,
+}
+
+impl FromStr for MapGen {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<MapGen, String> {
+        match name {
+            "maze" => Ok(MapGen::Maze),
+            "caves" => Ok(MapGen::Caves),
+            "rooms" => Ok(MapGen::Rooms),
+            _ => Err(format!("unknown map generator '{}'", name)),
+        }
+    }
+}
+
+/// The walls of a board while it is generated, `true` is a wall.
+struct Grid {
+    width: usize,
+    height: usize,
+    walls: Vec<bool>,
+}
+
+impl Grid {
+    fn new(width: usize, height: usize, wall: bool) -> Grid {
+        Grid {
+            width,
+            height,
+            walls: vec![wall; width * height],
+        }
+    }
+
+    fn is_inside(&self, row: usize, col: usize) -> bool {
+        row > 0 && col > 0 && row < self.height - 1 && col < self.width - 1
+    }
+
+    fn is_wall(&self, row: usize, col: usize) -> bool {
+        !self.is_inside(row, col) || self.walls[row * self.width + col]
+    }
+
+    fn set(&mut self, row: usize, col: usize, wall: bool) {
+        if self.is_inside(row, col) {
+            self.walls[row * self.width + col] = wall;
+        }
+    }
+
+    fn walls_around(&self, row: usize, col: usize) -> usize {
+        (row - 1..=row + 1)
+            .flat_map(|r| (col - 1..=col + 1).map(move |c| (r, c)))
+            .filter(|&(r, c)| self.is_wall(r, c))
+            .count()
+    }
+
+    fn to_level(&self, name: String) -> Level {
+        let spawn = Coordinate {
+            row: self.height - 2,
+            col: SPAWN_COL,
+        };
+        let grid = (0..self.height)
+            .map(|row| {
+                (0..self.width)
+                    .map(|col| {
+                        if (Coordinate { row, col }) == spawn {
+                            SPAWN
+                        } else if self.is_wall(row, col) {
+                            WALL
+                        } else {
+                            EMPTY
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Level {
+            name: Some(name),
+            grid,
+        }
+    }
+}
+
+/// Generates a board of walls from the seed, which is always the same for the same seed.
+///
+/// The rows of `texts`, laid out with `text_layout` inside `PADDING`, stay free, and every free
+/// cell can be reached from the spawn, so all of it is food that can be eaten.
+pub fn generate(
+    kind: MapGen,
+    width: usize,
+    height: usize,
+    seed: u64,
+    texts: &[&str],
+    text_layout: TextLayout,
+) -> Level {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = match kind {
+        MapGen::Maze => maze(width, height, &mut rng),
+        MapGen::Caves => caves(width, height, &mut rng),
+        MapGen::Rooms => rooms(width, height, &mut rng),
+    };
+
+    for text in texts {
+        clear_text(&mut grid, text, text_layout);
+    }
+    let row = height - 2;
+    for col in 1..=SPAWN_COL + RUNWAY {
+        grid.set(row, col, false);
+    }
+    connect(&mut grid);
+
+    let name = format!("{:?} {}", kind, seed).to_lowercase();
+    grid.to_level(name)
+}
+
+/// A maze of corridors without dead ends, which a snake couldn't get out of.
+fn maze(width: usize, height: usize, rng: &mut StdRng) -> Grid {
+    let mut grid = Grid::new(width, height, true);
+    // the rooms of the maze are the cells with odd coordinates, the others are walls between them
+    let is_room = |row: usize, col: usize| row % 2 == 1 && col % 2 == 1;
+    let neighbours = |grid: &Grid, row: usize, col: usize| {
+        let mut cells = vec![];
+        if row >= 2 {
+            cells.push((row - 2, col));
+        }
+        if col >= 2 {
+            cells.push((row, col - 2));
+        }
+        cells.push((row + 2, col));
+        cells.push((row, col + 2));
+        cells
+            .into_iter()
+            .filter(|&(r, c)| grid.is_inside(r, c) && is_room(r, c))
+            .collect::<Vec<_>>()
+    };
+
+    let mut stack = vec![(1, 1)];
+    grid.set(1, 1, false);
+    while let Some(&(row, col)) = stack.last() {
+        let unvisited: Vec<_> = neighbours(&grid, row, col)
+            .into_iter()
+            .filter(|&(r, c)| grid.is_wall(r, c))
+            .collect();
+        match unvisited.choose(rng) {
+            Some(&(r, c)) => {
+                grid.set((row + r) / 2, (col + c) / 2, false);
+                grid.set(r, c, false);
+                stack.push((r, c));
+            }
+            None => {
+                stack.pop();
+            }
+        }
+    }
+
+    for row in 1..height - 1 {
+        for col in 1..width - 1 {
+            if !is_room(row, col) {
+                continue;
+            }
+            let closed: Vec<_> = neighbours(&grid, row, col)
+                .into_iter()
+                .filter(|&(r, c)| grid.is_wall((row + r) / 2, (col + c) / 2))
+                .collect();
+            if closed.len() + 1 >= neighbours(&grid, row, col).len() {
+                if let Some(&(r, c)) = closed.choose(rng) {
+                    grid.set((row + r) / 2, (col + c) / 2, false);
+                }
+            }
+        }
+    }
+    grid
+}
+
+/// Caves grown from random noise, every cell becomes a wall when most cells around it are walls.
+fn caves(width: usize, height: usize, rng: &mut StdRng) -> Grid {
+    let mut grid = Grid::new(width, height, true);
+    for row in 1..height - 1 {
+        for col in 1..width - 1 {
+            grid.set(row, col, rng.gen_bool(CAVE_FILL));
+        }
+    }
+    for _ in 0..CAVE_STEPS {
+        let mut next = Grid::new(width, height, true);
+        for row in 1..height - 1 {
+            for col in 1..width - 1 {
+                next.set(row, col, grid.walls_around(row, col) >= 5);
+            }
+        }
+        grid = next;
+    }
+    grid
+}
+
+/// Rectangular rooms joined by corridors.
+fn rooms(width: usize, height: usize, rng: &mut StdRng) -> Grid {
+    let mut grid = Grid::new(width, height, true);
+    let count = (width * height / 150).max(2);
+    let mut centers = vec![];
+    for _ in 0..count {
+        let room_width = rng.gen_range(3, (width / 4).max(4));
+        let room_height = rng.gen_range(3, (height / 4).max(4));
+        let top = rng.gen_range(1, (height - 1).saturating_sub(room_height).max(2));
+        let left = rng.gen_range(1, (width - 1).saturating_sub(room_width).max(2));
+        for row in top..top + room_height {
+            for col in left..left + room_width {
+                grid.set(row, col, false);
+            }
+        }
+        centers.push((top + room_height / 2, left + room_width / 2));
+    }
+
+    for pair in centers.windows(2) {
+        let ((row1, col1), (row2, col2)) = (pair[0], pair[1]);
+        for col in col1.min(col2)..=col1.max(col2) {
+            grid.set(row1, col, false);
+        }
+        for row in row1.min(row2)..=row1.max(row2) {
+            grid.set(row, col2, false);
+        }
+    }
+    grid
+}
+
+fn clear_text(grid: &mut Grid, text: &str, text_layout: TextLayout) {
+    let mut buffer = ScreenBuffer::new(grid.width, grid.height, GameContent::Empty);
+    buffer.set_text_layout(text_layout);
+    if buffer.layout_text(text, PADDING).is_err() {
+        return;
+    }
+    buffer.fill_with_text(text.to_string(), PADDING);
+    for row in 0..grid.height {
+        for col in 0..grid.width {
+            // the spaces between the words stay free too, so the lines read as lines
+            if let GameContent::Character(_) = buffer.get_at(row, col) {
+                grid.set(row, col, false);
+            }
+        }
+    }
+}
+
+/// Carves the shortest way from every free cell that is sealed off to the cells around the spawn.
+fn connect(grid: &mut Grid) {
+    let spawn = Coordinate {
+        row: grid.height - 2,
+        col: SPAWN_COL,
+    };
+    loop {
+        let reachable = grid.to_level(String::new()).reachable_from(spawn);
+        let sealed = (0..grid.walls.len()).find(|&idx| {
+            grid.is_inside(idx / grid.width, idx % grid.width)
+                && !grid.walls[idx]
+                && !reachable[idx]
+        });
+        let start = match sealed {
+            Some(start) => start,
+            None => break,
+        };
+
+        let mut came_from = vec![None; grid.walls.len()];
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        came_from[start] = Some(start);
+        while let Some(idx) = queue.pop_front() {
+            if reachable[idx] {
+                let mut cell = idx;
+                while cell != start {
+                    grid.walls[cell] = false;
+                    cell = came_from[cell].unwrap_or(start);
+                }
+                break;
+            }
+            let (row, col) = (idx / grid.width, idx % grid.width);
+            for (r, c) in [
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ] {
+                let next = r * grid.width + c;
+                if grid.is_inside(r, c) && came_from[next].is_none() {
+                    came_from[next] = Some(idx);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+}
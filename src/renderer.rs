@@ -0,0 +1,463 @@
+use std::io::{stdout, Stdout, Write};
+use std::time::Instant;
+
+use crossterm::{
+    cursor,
+    style::{self, Color, Print, StyledContent},
+    terminal::{self, disable_raw_mode, enable_raw_mode},
+    ExecutableCommand, QueueableCommand, Result,
+};
+
+use crate::export::{css_color, html_escape};
+
+// \u{2588} is a full block symbol
+const BLOCK: &str = "\u{2588}";
+// the size of one terminal cell in SVG files
+const SVG_CELL_WIDTH: usize = 8;
+const SVG_CELL_HEIGHT: usize = 16;
+
+/// Something the board is drawn on, one terminal cell at a time.
+///
+/// The cells put between two flushes make up one frame.
+pub trait Renderer {
+    /// Gets ready to draw, e.g. puts the terminal into raw mode.
+    fn start(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Puts the content into the cell at the column and row, the way a terminal would.
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()>;
+
+    /// Replaces the whole row with the plain text, e.g. a status line below the board.
+    fn print_line(&mut self, row: u16, text: &str) -> Result<()>;
+
+    /// Shows the frame.
+    fn flush(&mut self) -> Result<()>;
+
+    /// Undoes what `start` did.
+    fn finish(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Draws on the terminal with crossterm.
+pub struct Terminal {
+    stdout: Stdout,
+}
+
+impl Terminal {
+    pub fn new() -> Terminal {
+        Terminal { stdout: stdout() }
+    }
+
+    /// The terminal itself, for the commands that aren't about drawing.
+    pub fn stdout(&mut self) -> &mut Stdout {
+        &mut self.stdout
+    }
+}
+
+impl Renderer for Terminal {
+    fn start(&mut self) -> Result<()> {
+        enable_raw_mode()?;
+        self.stdout.execute(cursor::Hide)?;
+        self.stdout
+            .execute(terminal::Clear(terminal::ClearType::All))?;
+        Ok(())
+    }
+
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()> {
+        self.stdout
+            .queue(cursor::MoveTo(col, row))?
+            .queue(style::PrintStyledContent(content))?;
+        Ok(())
+    }
+
+    fn print_line(&mut self, row: u16, text: &str) -> Result<()> {
+        self.stdout
+            .queue(cursor::MoveTo(0, row))?
+            .queue(terminal::Clear(terminal::ClearType::CurrentLine))?
+            .queue(Print(text))?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.stdout.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.stdout.execute(cursor::Show)?;
+        disable_raw_mode()
+    }
+}
+
+/// Keeps the characters of the frame in memory and forgets the colors.
+#[derive(Clone, Default)]
+pub struct TextGrid {
+    rows: Vec<Vec<char>>,
+}
+
+impl TextGrid {
+    pub fn new() -> TextGrid {
+        TextGrid::default()
+    }
+
+    fn row(&mut self, row: usize) -> &mut Vec<char> {
+        if self.rows.len() <= row {
+            self.rows.resize(row + 1, vec![]);
+        }
+        &mut self.rows[row]
+    }
+
+    fn cell(&mut self, col: usize, row: usize) -> &mut char {
+        let cells = self.row(row);
+        if cells.len() <= col {
+            cells.resize(col + 1, ' ');
+        }
+        &mut cells[col]
+    }
+
+    /// The rows of the frame, without the spaces at their ends.
+    pub fn text(&self) -> String {
+        self.rows
+            .iter()
+            .map(|cells| {
+                let line: String = cells.iter().collect();
+                format!("{}\n", line.trim_end())
+            })
+            .collect()
+    }
+}
+
+impl Renderer for TextGrid {
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()> {
+        for (idx, c) in content.content().chars().enumerate() {
+            *self.cell(col as usize + idx, row as usize) = c;
+        }
+        Ok(())
+    }
+
+    fn print_line(&mut self, row: u16, text: &str) -> Result<()> {
+        *self.row(row as usize) = text.chars().collect();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes every frame as rows of text with ANSI colors, the way `cat` shows them again.
+pub struct AnsiFile<W: Write> {
+    out: W,
+    rows: Vec<Vec<String>>,
+}
+
+impl<W: Write> AnsiFile<W> {
+    pub fn new(out: W) -> AnsiFile<W> {
+        AnsiFile { out, rows: vec![] }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// The row of the frame, with empty rows added up to it.
+fn row(rows: &mut Vec<Vec<String>>, row: usize) -> &mut Vec<String> {
+    if rows.len() <= row {
+        rows.resize(row + 1, vec![]);
+    }
+    &mut rows[row]
+}
+
+/// Puts the markup of a cell into the frame, the cells skipped before it get `blank`.
+fn put_cell(rows: &mut Vec<Vec<String>>, col: u16, row_idx: u16, cell: String, blank: &str) {
+    let cells = row(rows, row_idx as usize);
+    let col = col as usize;
+    if cells.len() <= col {
+        cells.resize(col + 1, blank.to_string());
+    }
+    cells[col] = cell;
+}
+
+impl<W: Write> Renderer for AnsiFile<W> {
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()> {
+        put_cell(&mut self.rows, col, row, content.to_string(), " ");
+        Ok(())
+    }
+
+    fn print_line(&mut self, row_idx: u16, text: &str) -> Result<()> {
+        *row(&mut self.rows, row_idx as usize) = text.chars().map(String::from).collect();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        for cells in self.rows.drain(..) {
+            writeln!(self.out, "{}", cells.concat())?;
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// The text of a cell and the colors it is drawn with, a full block becomes the background so
+/// that there are no gaps between the rows.
+fn cell_colors(content: &StyledContent<String>) -> (&str, Color, Color) {
+    let style = content.style();
+    let foreground = style.foreground_color.unwrap_or(Color::White);
+    if content.content() == BLOCK {
+        (
+            " ",
+            foreground,
+            style.foreground_color.unwrap_or(Color::Black),
+        )
+    } else {
+        let background = style.background_color.unwrap_or(Color::Black);
+        (content.content(), foreground, background)
+    }
+}
+
+/// Writes every frame as an HTML page, with a colored span for each terminal cell.
+pub struct HtmlFile<W: Write> {
+    out: W,
+    rows: Vec<Vec<String>>,
+}
+
+impl<W: Write> HtmlFile<W> {
+    pub fn new(out: W) -> HtmlFile<W> {
+        HtmlFile { out, rows: vec![] }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+impl<W: Write> Renderer for HtmlFile<W> {
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()> {
+        let (text, foreground, background) = cell_colors(&content);
+        let span = format!(
+            "<span style=\"color:{};background:{}\">{}</span>",
+            css_color(foreground),
+            css_color(background),
+            html_escape(text)
+        );
+        put_cell(&mut self.rows, col, row, span, " ");
+        Ok(())
+    }
+
+    fn print_line(&mut self, row_idx: u16, text: &str) -> Result<()> {
+        *row(&mut self.rows, row_idx as usize) = vec![html_escape(text)];
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        write!(
+            self.out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>rs_snake</title>\n\
+             <style>pre {{ font-family: monospace; line-height: 1; }}</style>\n</head>\n<body>\n<pre>\n"
+        )?;
+        for cells in self.rows.drain(..) {
+            writeln!(self.out, "{}", cells.concat())?;
+        }
+        write!(self.out, "</pre>\n</body>\n</html>\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes every frame as an SVG image, with a colored rectangle and the text for each terminal
+/// cell.
+pub struct SvgFile<W: Write> {
+    out: W,
+    rows: Vec<Vec<String>>,
+}
+
+impl<W: Write> SvgFile<W> {
+    pub fn new(out: W) -> SvgFile<W> {
+        SvgFile { out, rows: vec![] }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// The SVG text element at the position of the cell.
+fn svg_text(col: usize, row: usize, color: Color, text: &str) -> String {
+    format!(
+        "<text x=\"{}\" y=\"{}\" fill=\"{}\">{}</text>",
+        col * SVG_CELL_WIDTH,
+        (row + 1) * SVG_CELL_HEIGHT - 4,
+        css_color(color),
+        html_escape(text)
+    )
+}
+
+impl<W: Write> Renderer for SvgFile<W> {
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()> {
+        let (text, foreground, background) = cell_colors(&content);
+        let mut cell = format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+            col as usize * SVG_CELL_WIDTH,
+            row as usize * SVG_CELL_HEIGHT,
+            SVG_CELL_WIDTH,
+            SVG_CELL_HEIGHT,
+            css_color(background)
+        );
+        if !text.trim().is_empty() {
+            cell.push_str(&svg_text(col as usize, row as usize, foreground, text));
+        }
+        put_cell(&mut self.rows, col, row, cell, "");
+        Ok(())
+    }
+
+    fn print_line(&mut self, row_idx: u16, text: &str) -> Result<()> {
+        let line = svg_text(0, row_idx as usize, Color::White, text);
+        *row(&mut self.rows, row_idx as usize) = vec![line];
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        writeln!(
+            self.out,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+             viewBox=\"0 0 {0} {1}\" font-family=\"monospace\" font-size=\"{2}\">",
+            columns * SVG_CELL_WIDTH,
+            self.rows.len() * SVG_CELL_HEIGHT,
+            SVG_CELL_HEIGHT - 2
+        )?;
+        for cells in self.rows.drain(..) {
+            writeln!(self.out, "{}", cells.concat())?;
+        }
+        writeln!(self.out, "</svg>")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// Draws on another renderer and writes the text of every frame that changed into `out`, with
+/// the time it was shown since the start.
+pub struct Recording<R: Renderer, W: Write> {
+    inner: R,
+    grid: TextGrid,
+    last_frame: String,
+    start: Instant,
+    out: W,
+}
+
+impl<R: Renderer, W: Write> Recording<R, W> {
+    pub fn new(inner: R, out: W) -> Recording<R, W> {
+        Recording {
+            inner,
+            grid: TextGrid::new(),
+            last_frame: String::new(),
+            start: Instant::now(),
+            out,
+        }
+    }
+}
+
+impl<R: Renderer, W: Write> Renderer for Recording<R, W> {
+    fn start(&mut self) -> Result<()> {
+        self.start = Instant::now();
+        self.inner.start()
+    }
+
+    fn put(&mut self, col: u16, row: u16, content: StyledContent<String>) -> Result<()> {
+        self.grid.put(col, row, content.clone())?;
+        self.inner.put(col, row, content)
+    }
+
+    fn print_line(&mut self, row: u16, text: &str) -> Result<()> {
+        self.grid.print_line(row, text)?;
+        self.inner.print_line(row, text)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()?;
+        let frame = self.grid.text();
+        if frame != self.last_frame {
+            writeln!(self.out, "--- {:.3}s", self.start.elapsed().as_secs_f64())?;
+            self.out.write_all(frame.as_bytes())?;
+            self.last_frame = frame;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        self.inner.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headless::assert_snapshot;
+    use crate::screen_buffer::{GameContent, ScreenBuffer};
+    use crate::theme::{Shapes, Theme};
+
+    /// The border around the snake, a letter and a food.
+    fn board() -> ScreenBuffer {
+        let mut board = ScreenBuffer::new(6, 3, GameContent::Empty);
+        board.add_border(GameContent::Border);
+        board.set_at(1, 1, GameContent::SnakeHead);
+        board.set_at(1, 2, GameContent::SnakeBody);
+        board.set_at(1, 3, GameContent::Character('<'));
+        board.set_at(1, 4, GameContent::Food);
+        board
+    }
+
+    #[test]
+    fn text_grid_keeps_the_characters() {
+        let theme = Theme {
+            shapes: Shapes::Ascii,
+            ..Theme::classic()
+        };
+        let mut grid = TextGrid::new();
+        board().draw(&mut grid, &theme).unwrap();
+        grid.print_line(3, "status").unwrap();
+        assert_eq!(
+            grid.text(),
+            "++++++++++++\n++@ = < . ++\n++++++++++++\nstatus\n"
+        );
+    }
+
+    #[test]
+    fn glyphs_tell_the_snake_apart_on_the_message() {
+        let theme = Theme {
+            shapes: Shapes::Glyphs,
+            ..Theme::classic()
+        };
+        let mut board = ScreenBuffer::new(3, 1, GameContent::Empty);
+        board.set_at(0, 0, GameContent::SnakeHeadWithChar('h'));
+        board.set_at(0, 1, GameContent::SnakeBodyWithChar('i'));
+        board.set_at(0, 2, GameContent::Character('!'));
+        let mut grid = TextGrid::new();
+        board.draw(&mut grid, &theme).unwrap();
+        assert_eq!(grid.text(), "@ o !\u{2588}\n");
+    }
+
+    #[test]
+    fn html_file_colors_every_cell() {
+        let mut file = HtmlFile::new(vec![]);
+        board().draw(&mut file, &Theme::classic()).unwrap();
+        assert_snapshot(
+            "renderer_html",
+            &String::from_utf8(file.into_inner()).unwrap(),
+        );
+    }
+
+    #[test]
+    fn svg_file_colors_every_cell() {
+        let mut file = SvgFile::new(vec![]);
+        board().draw(&mut file, &Theme::classic()).unwrap();
+        assert_snapshot(
+            "renderer_svg",
+            &String::from_utf8(file.into_inner()).unwrap(),
+        );
+    }
+}
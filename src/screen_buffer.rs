@@ -1,12 +1,16 @@
-use std::io::Write;
+use std::io;
+use std::str::FromStr;
 
 use crossterm::{
-    cursor::{self},
-    style::{self, Colorize, StyledContent},
-    QueueableCommand, Result,
+    style::{self, Color, StyledContent},
+    Result,
 };
 
-#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+use crate::i18n::visual_order;
+use crate::renderer::Renderer;
+use crate::theme::{Shapes, Theme};
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum GameContent {
     SnakeHead,
     SnakeHeadWithChar(char),
@@ -17,44 +21,92 @@ pub enum GameContent {
     BorderWithChar(char),
     Empty,
     Character(char),
+    Wall,
+    Portal,
+    /// Bonus points, and it reveals the food around it.
+    GoldenFood,
+    /// Shrinks the snake.
+    Poison,
+    /// Speeds the snake up for a while.
+    SpeedFood,
+    /// Slows the snake down for a while.
+    SlowFood,
+    /// Power-up that eats the food in a radius around the head.
+    Bomb,
+    /// Power-up that eats the food in the row or column the snake moves along.
+    Line,
+    /// Power-up that eats the food next to the head for a while.
+    Magnet,
 }
 
-fn map_game_content_to_color(gc: &GameContent, is_padded_char: bool) -> StyledContent<String> {
-    // \u{2588} is a full block symbol
-    match gc {
-        GameContent::SnakeHead => "\u{2588}".to_string().dark_green(),
-        GameContent::SnakeHeadWithChar(char) => {
-            if is_padded_char {
-                "\u{2588}".to_string().dark_green()
-            } else {
-                char.to_string().black().on_dark_green()
-            }
-        }
-        GameContent::SnakeBody => "\u{2588}".to_string().green(),
-        GameContent::SnakeBodyWithChar(char) => {
-            if is_padded_char {
-                "\u{2588}".to_string().green()
-            } else {
-                char.to_string().black().on_green()
-            }
-        }
-        GameContent::Food => "\u{2588}".to_string().black(),
-        GameContent::Border => "\u{2588}".to_string().dark_blue(),
-        GameContent::BorderWithChar(char) => {
-            if is_padded_char {
-                "\u{2588}".to_string().dark_blue()
-            } else {
-                char.to_string().white().on_dark_blue()
-            }
+impl GameContent {
+    /// Whether the snake eats this cell, i.e. whether it still hides a part of the message.
+    pub fn is_food(&self) -> bool {
+        matches!(self, GameContent::Food)
+    }
+
+    /// Whether this is one of the special foods that show up on the eaten part of the board.
+    pub fn is_special_food(&self) -> bool {
+        matches!(
+            self,
+            GameContent::GoldenFood
+                | GameContent::Poison
+                | GameContent::SpeedFood
+                | GameContent::SlowFood
+        )
+    }
+
+    /// The character drawn on this cell, if any.
+    pub fn character(&self) -> Option<char> {
+        match self {
+            GameContent::SnakeHeadWithChar(c)
+            | GameContent::SnakeBodyWithChar(c)
+            | GameContent::BorderWithChar(c)
+            | GameContent::Character(c) => Some(*c),
+            _ => None,
         }
-        GameContent::Empty => "\u{2588}".to_string().white(),
-        GameContent::Character(some_char) => {
-            if is_padded_char {
-                "\u{2588}".to_string().white()
+    }
+
+    pub fn is_power_up(&self) -> bool {
+        matches!(
+            self,
+            GameContent::Bomb | GameContent::Line | GameContent::Magnet
+        )
+    }
+}
+
+pub fn map_game_content_to_color(
+    gc: &GameContent,
+    is_padded_char: bool,
+    theme: &Theme,
+) -> StyledContent<String> {
+    if theme.shapes == Shapes::Ascii {
+        // only the characters tell the cells apart, the walls and the border stay closed lines
+        let c = match theme.glyph(gc).or(gc.character()) {
+            Some(c) if !is_padded_char => c,
+            Some(c) if matches!(gc, GameContent::Wall | GameContent::Border) => c,
+            _ => ' ',
+        };
+        return style::style(c.to_string());
+    }
+    // \u{2588} is a full block symbol
+    let block = style::style("\u{2588}".to_string()).with(theme.color(gc));
+    match (gc.character(), theme.glyph(gc)) {
+        (_, Some(glyph)) => {
+            // walls stay closed lines, everything else gets a gap so that the glyphs stand out
+            let glyph = if is_padded_char && !matches!(gc, GameContent::Wall) {
+                ' '
             } else {
-                some_char.to_string().black().on_white()
-            }
+                glyph
+            };
+            style::style(glyph.to_string())
+                .with(theme.color(gc))
+                .on(theme.empty)
         }
+        (Some(c), _) if !is_padded_char => style::style(c.to_string())
+            .with(theme.text_color(gc))
+            .on(theme.color(gc)),
+        _ => block,
     }
 }
 
@@ -64,11 +116,62 @@ pub struct Coordinate {
     pub col: usize,
 }
 
-#[derive(serde::Serialize, serde::Deserialize)]
+/// Where the rows of the message sit between the padding on both sides.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Align {
+    #[default]
+    Center,
+    Left,
+    /// Widens the spaces between the words until the rows are as wide as a full row, except for
+    /// the last row of a paragraph, which is aligned left.
+    Justify,
+}
+
+impl FromStr for Align {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Align, String> {
+        match name {
+            "center" => Ok(Align::Center),
+            "left" => Ok(Align::Left),
+            "justify" => Ok(Align::Justify),
+            _ => Err(format!("unknown alignment '{}'", name)),
+        }
+    }
+}
+
+/// How `layout_text` breaks the message into rows.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TextLayout {
+    pub align: Align,
+    /// Ends the rows of a word that is too long for a row of its own with a hyphen.
+    pub hyphenate: bool,
+    /// Keeps runs of spaces between the words and at the start of a paragraph, instead of
+    /// putting a single space between the words.
+    pub keep_spaces: bool,
+    /// Lays out the rows of the text this many board rows apart, e.g. 2 for half-block drawing.
+    pub line_spacing: usize,
+}
+
+impl Default for TextLayout {
+    fn default() -> TextLayout {
+        TextLayout {
+            align: Align::default(),
+            hyphenate: false,
+            keep_spaces: false,
+            line_spacing: 1,
+        }
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct ScreenBuffer {
     screen_width: usize,
     screen_height: usize,
     buffer: Vec<GameContent>,
+    // how the text is laid out depends on how the buffer is drawn, so it isn't saved
+    #[serde(skip)]
+    text_layout: TextLayout,
 }
 
 impl ScreenBuffer {
@@ -81,9 +184,30 @@ impl ScreenBuffer {
             screen_height,
             screen_width,
             buffer: vec![initial_content; screen_height * screen_width],
+            text_layout: TextLayout::default(),
         }
     }
 
+    pub fn line_spacing(&self) -> usize {
+        self.text_layout.line_spacing
+    }
+
+    /// See `TextLayout::line_spacing`.
+    pub fn set_line_spacing(&mut self, line_spacing: usize) {
+        self.text_layout.line_spacing = line_spacing.max(1);
+    }
+
+    pub fn set_text_layout(&mut self, text_layout: TextLayout) {
+        self.text_layout = text_layout;
+        self.set_line_spacing(text_layout.line_spacing);
+    }
+
+    /// The number of board rows the text rows take with the line spacing.
+    fn text_height(&self, rows: usize) -> usize {
+        let line_spacing = self.line_spacing();
+        (rows * line_spacing).saturating_sub(line_spacing - 1)
+    }
+
     pub fn height(&self) -> usize {
         self.screen_height
     }
@@ -98,60 +222,126 @@ impl ScreenBuffer {
         }
     }
 
-    pub fn fill_with_text(&mut self, text: String, padding: usize) {
+    /// Splits `text` into the rows `fill_with_text` would draw, failing when the text does not
+    /// fit into the box left inside `padding`. Words that are wider than the box are broken up.
+    pub fn layout_text(&self, text: &str, padding: usize) -> io::Result<Vec<String>> {
+        if self.screen_width <= 2 * padding || self.screen_height <= 2 * padding {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "a {}x{} screen has no room for text with padding {}",
+                    self.screen_width, self.screen_height, padding
+                ),
+            ));
+        }
         let allowed_width = self.screen_width - 2 * padding;
         let allowed_height = self.screen_height - 2 * padding;
-        let split: Vec<_> = text.split(' ').collect();
+        let layout = self.text_layout;
+        let paragraphs: Vec<&str> = text.split('\n').collect();
         let mut rows = vec![];
 
-        let mut row = String::new();
-        for word in split {
-            if word.contains("\n") {
-                for word in word.split("\n") {
-                    if !word.is_empty() {
-                        if row.len() + word.len() + 1 /* whitespace */ < allowed_width {
-                            row.push_str(" ");
-                            row.push_str(word);
-                        } else {
-                            rows.push(row);
-                            row = word.to_string();
-                        }
+        for (idx, paragraph) in paragraphs.iter().enumerate() {
+            let mut row = String::new();
+            let mut is_first_row = true;
+            // the spaces in front of the next word, beyond the one between two words
+            let mut extra_spaces = 0;
+            for word in paragraph.split(' ') {
+                if word.is_empty() {
+                    if layout.keep_spaces {
+                        extra_spaces += 1;
                     }
-                    rows.push(row);
-                    row = String::new();
+                    continue;
                 }
-                row = rows.pop().unwrap();
-                continue;
-            }
-
-            if !word.is_empty() {
-                if row.len() + word.len() + 1 /* whitespace */ < allowed_width {
-                    row.push_str(" ");
+                let spaces = match (row.is_empty(), is_first_row) {
+                    (false, _) => 1 + extra_spaces,
+                    // spaces at the start of a paragraph are kept, at a line break they aren't
+                    (true, true) if layout.keep_spaces => extra_spaces,
+                    // centered paragraphs have always started with a space, so the letters of
+                    // saved games stay where they were revealed
+                    (true, true) if layout.align == Align::Center => 1,
+                    (true, _) => 0,
+                };
+                extra_spaces = 0;
+                let word_len = word.chars().count();
+                // a row of whole words leaves its last cell free
+                if row.chars().count() + spaces + word_len < allowed_width {
+                    row.push_str(&" ".repeat(spaces));
                     row.push_str(word);
-                } else {
-                    rows.push(row);
-                    row = word.to_string();
+                    continue;
                 }
+                if !row.is_empty() {
+                    rows.push(if layout.align == Align::Justify {
+                        justify(&row, allowed_width - 1)
+                    } else {
+                        row
+                    });
+                    is_first_row = false;
+                }
+                row = if word_len <= allowed_width {
+                    word.to_string()
+                } else {
+                    // a word that doesn't fit into a row of its own is broken up
+                    let mut chars: Vec<char> = word.chars().collect();
+                    let hyphenate = layout.hyphenate && allowed_width > 1;
+                    let piece_len = allowed_width - usize::from(hyphenate);
+                    while chars.len() > allowed_width {
+                        let mut piece: String = chars.drain(..piece_len).collect();
+                        if hyphenate {
+                            piece.push('-');
+                        }
+                        rows.push(piece);
+                    }
+                    is_first_row = false;
+                    chars.into_iter().collect()
+                };
+            }
+            // the last row of a paragraph ends it, an empty one is only kept between paragraphs
+            if idx + 1 < paragraphs.len() || !row.is_empty() {
+                rows.push(row);
             }
         }
-        if !row.is_empty() {
-            rows.push(row);
+
+        if self.text_height(rows.len()) > allowed_height {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "the text needs {} rows but only {} fit",
+                    self.text_height(rows.len()),
+                    allowed_height
+                ),
+            ));
         }
-        assert!(rows.len() <= allowed_height);
-        let padding_top = padding as usize + (allowed_height - rows.len()) / 2;
-        for i in 0..rows.len() {
-            self.set_centered_text_at_row(padding_top + i, rows[i].as_str());
+        Ok(rows)
+    }
+
+    pub fn fill_with_text(&mut self, text: String, padding: usize) {
+        let rows = self
+            .layout_text(&text, padding)
+            .expect("The text doesn't fit the screen");
+        let allowed_height = self.screen_height - 2 * padding;
+        let padding_top = padding + (allowed_height - self.text_height(rows.len())) / 2;
+        for (i, row) in rows.iter().enumerate() {
+            let target_row = padding_top + i * self.line_spacing();
+            match self.text_layout.align {
+                Align::Center => self.set_centered_text_at_row(target_row, row.as_str()),
+                Align::Left | Align::Justify => {
+                    self.set_text_at_row(target_row, padding, row.as_str())
+                }
+            }
         }
     }
 
     pub fn set_centered_text_at_row(&mut self, target_row: usize, message: &str) {
-        let str_chars = message.chars();
-        let str_len = str_chars.clone().count();
-        let header_start_idx = ((self.screen_width - str_len) / 2usize).max(0);
-
-        let mut col_idx = header_start_idx;
+        let str_len = message.chars().count();
+        // a message wider than the screen is cut off instead of running into the next row
+        let header_start_idx = self.screen_width.saturating_sub(str_len) / 2usize;
+        self.set_text_at_row(target_row, header_start_idx, message);
+    }
 
-        for sym in str_chars {
+    /// Writes the message into the row from the column on, and cuts it off at the end of the row.
+    pub fn set_text_at_row(&mut self, target_row: usize, start_col: usize, message: &str) {
+        let message = visual_order(message);
+        for (col_idx, sym) in (start_col..self.screen_width).zip(message.chars()) {
             let content = match self.get_at(target_row, col_idx) {
                 GameContent::SnakeHead => GameContent::SnakeHeadWithChar(sym),
                 GameContent::SnakeBody => GameContent::SnakeBodyWithChar(sym),
@@ -162,9 +352,35 @@ impl ScreenBuffer {
                 GameContent::SnakeHeadWithChar(_) => GameContent::SnakeHeadWithChar(sym),
                 GameContent::SnakeBodyWithChar(_) => GameContent::SnakeBodyWithChar(sym),
                 GameContent::BorderWithChar(_) => GameContent::BorderWithChar(sym),
+                GameContent::Wall => GameContent::Wall,
+                GameContent::Portal => GameContent::Portal,
+                // special food and power-ups hide a letter until they are eaten
+                special @ (GameContent::GoldenFood
+                | GameContent::Poison
+                | GameContent::SpeedFood
+                | GameContent::SlowFood
+                | GameContent::Bomb
+                | GameContent::Line
+                | GameContent::Magnet) => special,
             };
             self.set_at(target_row, col_idx, content);
-            col_idx += 1;
+        }
+    }
+
+    pub fn count_food(&self) -> usize {
+        self.buffer
+            .iter()
+            .filter(|content| content.is_food())
+            .count()
+    }
+
+    /// Forgets the text drawn on revealed cells, e.g. before a message in another language is laid
+    /// out over them.
+    pub fn clear_characters(&mut self) {
+        for screen_char in &mut self.buffer {
+            if let GameContent::Character(_) = screen_char {
+                *screen_char = GameContent::Empty;
+            }
         }
     }
 
@@ -187,7 +403,20 @@ impl ScreenBuffer {
         }
     }
 
-    pub fn draw(&self, stdout: &mut std::io::Stdout) -> Result<()> {
+    /// Draws the border with every other cell left as `gap_symbol`.
+    pub fn add_dashed_border(&mut self, border_symbol: GameContent, gap_symbol: GameContent) {
+        self.add_border(border_symbol);
+        for row in (1..self.screen_height).step_by(2) {
+            self.set_at(row, 0, gap_symbol);
+            self.set_at(row, self.screen_width - 1, gap_symbol);
+        }
+        for col in (1..self.screen_width).step_by(2) {
+            self.set_at(0, col, gap_symbol);
+            self.set_at(self.screen_height - 1, col, gap_symbol);
+        }
+    }
+
+    pub fn draw(&self, renderer: &mut dyn Renderer, theme: &Theme) -> Result<()> {
         for row_idx in 0..self.screen_height {
             for col_idx_buffer in 0..self.screen_width {
                 let content = self.get_at(row_idx, col_idx_buffer);
@@ -195,14 +424,245 @@ impl ScreenBuffer {
                 for i in 0..2 {
                     let col_idx = 2 * col_idx_buffer + i;
 
-                    let styled_content = map_game_content_to_color(&content, i != 0);
-                    stdout
-                        .queue(cursor::MoveTo(col_idx as u16, row_idx as u16))?
-                        .queue(style::PrintStyledContent(styled_content))?;
+                    let styled_content = map_game_content_to_color(&content, i != 0, theme);
+                    renderer.put(col_idx as u16, row_idx as u16, styled_content)?;
                 }
             }
         }
-        stdout.flush()?;
-        Ok(())
+        renderer.flush()
+    }
+
+    /// Draws two board rows into one terminal row with half blocks, one terminal column per cell.
+    ///
+    /// A terminal cell shows a letter if either of its board cells has one, so the text has to be
+    /// laid out with a line spacing of 2 to stay readable. The other board cell shows through
+    /// behind the letter, and the spaces between the words don't hide it at all.
+    pub fn draw_half_blocks(&self, renderer: &mut dyn Renderer, theme: &Theme) -> Result<()> {
+        for row_idx in 0..self.half_block_height() {
+            for col_idx in 0..self.screen_width {
+                let top = self.get_at(2 * row_idx, col_idx);
+                let bottom = if 2 * row_idx + 1 < self.screen_height {
+                    Some(self.get_at(2 * row_idx + 1, col_idx))
+                } else {
+                    None
+                };
+                let has_letter =
+                    |content: &GameContent| content.character().is_some_and(|c| c != ' ');
+                let (letter, other) = if has_letter(&top) {
+                    (Some(&top), bottom.as_ref())
+                } else if bottom.as_ref().is_some_and(has_letter) {
+                    (bottom.as_ref(), Some(&top))
+                } else {
+                    (None, None)
+                };
+                let styled_content = match letter {
+                    Some(content) => {
+                        let styled = map_game_content_to_color(content, false, theme);
+                        match (content, other) {
+                            // a letter on the empty board, e.g. food or the snake can be behind it
+                            (GameContent::Character(_), Some(other)) => {
+                                styled.on(theme.color(other))
+                            }
+                            _ => styled,
+                        }
+                    }
+                    None => {
+                        let color = |content: &GameContent| theme.color(content);
+                        let bottom_color = bottom.as_ref().map_or(Color::Reset, color);
+                        // \u{2580} is the upper half block
+                        style::style("\u{2580}".to_string())
+                            .with(color(&top))
+                            .on(bottom_color)
+                    }
+                };
+                renderer.put(col_idx as u16, row_idx as u16, styled_content)?;
+            }
+        }
+        renderer.flush()
+    }
+
+    /// The number of terminal rows `draw_half_blocks` uses.
+    pub fn half_block_height(&self) -> usize {
+        self.screen_height.div_ceil(2)
+    }
+}
+
+/// Widens the spaces between the words of the row until it is `width` characters wide, the first
+/// spaces get what can't be spread evenly.
+fn justify(row: &str, width: usize) -> String {
+    let words: Vec<&str> = row.split(' ').filter(|word| !word.is_empty()).collect();
+    if words.len() < 2 {
+        return row.to_string();
+    }
+    let letters: usize = words.iter().map(|word| word.chars().count()).sum();
+    let gaps = words.len() - 1;
+    let spaces = width.saturating_sub(letters).max(gaps);
+    let mut justified = String::new();
+    for (idx, word) in words.iter().enumerate() {
+        if idx > 0 {
+            let wider = usize::from(idx <= spaces % gaps);
+            justified.push_str(&" ".repeat(spaces / gaps + wider));
+        }
+        justified.push_str(word);
+    }
+    justified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // letters from both directions, spaces, newlines and the odd long word
+    const TEXT: &str = "([a-z\u{5d0}-\u{5ea}]{1,12}[ \n]{1,3}){0,30}";
+    // few enough words to fit most boards
+    const SHORT_TEXT: &str = "([a-z\u{5d0}-\u{5ea}]{1,8}[ \n]{1,2}){0,8}";
+
+    fn text_layout() -> impl Strategy<Value = TextLayout> {
+        let align = prop_oneof![Just(Align::Center), Just(Align::Left), Just(Align::Justify)];
+        (align, any::<bool>(), any::<bool>(), 1..3usize).prop_map(
+            |(align, hyphenate, keep_spaces, line_spacing)| TextLayout {
+                align,
+                hyphenate,
+                keep_spaces,
+                line_spacing,
+            },
+        )
+    }
+
+    fn layout(width: usize, text_layout: TextLayout, text: &str) -> Vec<String> {
+        let mut buffer = ScreenBuffer::new(width + 2, 1000, GameContent::Empty);
+        buffer.set_text_layout(text_layout);
+        buffer.layout_text(text, 1).unwrap()
+    }
+
+    #[test]
+    fn long_words_are_broken_up() {
+        let word = "Donaudampfschifffahrt";
+        assert_eq!(
+            layout(8, TextLayout::default(), word),
+            vec!["Donaudam", "pfschiff", "fahrt"]
+        );
+        let hyphenate = TextLayout {
+            hyphenate: true,
+            ..TextLayout::default()
+        };
+        assert_eq!(
+            layout(8, hyphenate, &format!("a {} b", word)),
+            vec![" a", "Donauda-", "mpfschi-", "fffahrt", "b"]
+        );
+    }
+
+    #[test]
+    fn spaces_are_kept_on_request() {
+        let text = "  a   b\n c";
+        assert_eq!(layout(10, TextLayout::default(), text), vec![" a b", " c"]);
+        let keep_spaces = TextLayout {
+            keep_spaces: true,
+            ..TextLayout::default()
+        };
+        assert_eq!(layout(10, keep_spaces, text), vec!["  a   b", " c"]);
+    }
+
+    #[test]
+    fn justified_rows_fill_the_width() {
+        let justify = TextLayout {
+            align: Align::Justify,
+            ..TextLayout::default()
+        };
+        assert_eq!(
+            layout(11, justify, "one two three four\nfive six"),
+            vec!["one    two", "three four", "five six"]
+        );
+    }
+
+    fn changed_cells(before: &ScreenBuffer, after: &ScreenBuffer) -> Vec<Coordinate> {
+        let mut changed = vec![];
+        for row in 0..before.height() {
+            for col in 0..before.width() {
+                if before.get_at(row, col) != after.get_at(row, col) {
+                    changed.push(Coordinate { row, col });
+                }
+            }
+        }
+        changed
+    }
+
+    proptest! {
+        #[test]
+        fn centered_text_stays_in_its_row(
+            width in 1..30usize,
+            height in 1..10usize,
+            row in 0..10usize,
+            message in "\\PC{0,40}",
+        ) {
+            let row = row % height;
+            let before = ScreenBuffer::new(width, height, GameContent::Empty);
+            let mut after = before.clone();
+            after.set_centered_text_at_row(row, &message);
+            for cell in changed_cells(&before, &after) {
+                prop_assert_eq!(cell.row, row);
+            }
+        }
+
+        #[test]
+        fn layout_fits_the_padding_box(
+            width in 1..40usize,
+            height in 1..20usize,
+            padding in 0..5usize,
+            text_layout in text_layout(),
+            text in TEXT,
+        ) {
+            let mut buffer = ScreenBuffer::new(width, height, GameContent::Empty);
+            buffer.set_text_layout(text_layout);
+            if let Ok(rows) = buffer.layout_text(&text, padding) {
+                for row in &rows {
+                    prop_assert!(row.chars().count() <= width - 2 * padding);
+                }
+                prop_assert!(buffer.text_height(rows.len()) <= height - 2 * padding);
+            }
+        }
+
+        #[test]
+        fn layout_keeps_the_words(
+            width in 1..40usize,
+            keep_spaces in any::<bool>(),
+            text in TEXT,
+        ) {
+            let text_layout = TextLayout {
+                keep_spaces,
+                ..TextLayout::default()
+            };
+            let rows = layout(width, text_layout, &text);
+            let letters = |text: &str| -> String {
+                text.chars().filter(|c| !c.is_whitespace()).collect()
+            };
+            prop_assert_eq!(letters(&rows.concat()), letters(&text));
+            // only the words that don't fit into a row are broken up
+            for word in text.split_whitespace() {
+                if word.chars().count() <= width {
+                    prop_assert!(rows.iter().any(|row| row.split(' ').any(|w| w == word)));
+                }
+            }
+        }
+
+        #[test]
+        fn text_is_only_written_inside_the_padding(
+            width in 10..40usize,
+            height in 10..30usize,
+            padding in 0..5usize,
+            text_layout in text_layout(),
+            text in SHORT_TEXT,
+        ) {
+            let mut before = ScreenBuffer::new(width, height, GameContent::Empty);
+            before.set_text_layout(text_layout);
+            prop_assume!(before.layout_text(&text, padding).is_ok());
+            let mut after = before.clone();
+            after.fill_with_text(text, padding);
+            for cell in changed_cells(&before, &after) {
+                prop_assert!(padding <= cell.row && cell.row < height - padding);
+                prop_assert!(padding <= cell.col && cell.col < width - padding);
+            }
+        }
     }
 }
@@ -1,19 +1,27 @@
-use std::io::stdout;
+use std::collections::VecDeque;
+use std::io;
+use std::str::FromStr;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossterm::{
-    cursor::{self},
     event::{KeyCode, KeyEvent},
-    terminal::{self, disable_raw_mode, enable_raw_mode},
-    ExecutableCommand, Result,
+    Result,
 };
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use crate::difficulty::{AdaptiveSpeed, Difficulty};
 use crate::events::{send_events, KeyEventQueue};
-use crate::screen_buffer::{Coordinate, GameContent, ScreenBuffer};
+use crate::export::{self, Format};
+use crate::frame_stats::FrameStats;
+use crate::i18n::{self, Message};
+use crate::level::Level;
+use crate::renderer::Renderer;
+use crate::screen_buffer::{Coordinate, GameContent, ScreenBuffer, TextLayout};
+use crate::theme::Theme;
 
-const TEXT: &'static str =
+pub const TEXT: &str =
     "Hello, my dear Hlib. I hope you are well. Today is your birthday, and I wish you all the best.
 
 I wish good health to you and your family. I hope they will be untouchable by the war as Enchantress from Dota 2.
@@ -24,90 +32,873 @@ I wish you to launch the mainnet soon and without any trouble. I hope it will wo
 
 I wish you to fully enjoin life.
 ";
-const PADDING: usize = 4;
+pub const PADDING: usize = 4;
+const INITIAL_LENGTH: usize = 3;
+// how much faster the classic mode gets with every food, and how fast any mode gets at most
+const CLASSIC_SPEED_UP: f64 = 0.02;
+const MAX_SPEED_UP: f64 = 2.0;
+// the chance per move that a special food shows up, and how many there can be at once
+const SPECIAL_FOOD_CHANCE: f64 = 0.005;
+const MAX_SPECIAL_FOOD: usize = 3;
+const GOLDEN_POINTS: usize = 5;
+const GOLDEN_RADIUS: usize = 2;
+const POISON_SHRINK: usize = 3;
+const SPEED_EFFECT: f64 = 1.5;
+const SLOW_EFFECT: f64 = 0.6;
+const EFFECT_DURATION: Duration = Duration::from_secs(5);
+const NOTICE_DURATION: Duration = Duration::from_secs(2);
+// how many moves the snake catches up with at most after the game got stuck
+const MAX_CATCH_UP_MOVES: u32 = 5;
+const MAX_POWER_UPS: usize = 2;
+const BOMB_RADIUS: usize = 4;
+// for how many moves the magnet eats the food next to the head
+const MAGNET_MOVES: usize = 60;
+// how many turns are kept for the next moves when the keys come faster than the snake moves
+const MAX_QUEUED_TURNS: usize = 3;
 
-#[derive(serde::Serialize, serde::Deserialize)]
+fn default_message() -> Message {
+    Message::Single(TEXT.to_string())
+}
+
+fn default_special_food_chance() -> f64 {
+    SPECIAL_FOOD_CHANCE
+}
+
+fn default_rng() -> StdRng {
+    StdRng::from_entropy()
+}
+
+fn default_locale() -> String {
+    i18n::DEFAULT_LOCALE.to_string()
+}
+
+/// What happens when the snake hits the border or itself.
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub enum Mode {
+    /// You can't die, the snake just starts over somewhere else.
+    #[default]
+    Forgiving,
+    /// Every collision costs a life, and the game is over when there are none left.
+    Survival,
+    /// The classic rules without a message: one food at a time, the snake grows and speeds up,
+    /// and the first collision is the end of the game.
+    Classic,
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Mode, String> {
+        match name {
+            "forgiving" => Ok(Mode::Forgiving),
+            "survival" => Ok(Mode::Survival),
+            "classic" => Ok(Mode::Classic),
+            _ => Err(format!("unknown mode '{}'", name)),
+        }
+    }
+}
+
+/// What a collision costs in the forgiving mode.
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub enum Penalty {
+    /// The snake starts over with the initial length somewhere else.
+    #[default]
+    Reset,
+    /// The snake loses this many segments and keeps going nearby.
+    Shrink(usize),
+    /// The snake loses half of its length and keeps going nearby.
+    Halve,
+}
+
+impl Penalty {
+    pub fn parse(name: &str, shrink_by: usize) -> std::result::Result<Penalty, String> {
+        match name {
+            "reset" => Ok(Penalty::Reset),
+            "shrink" => Ok(Penalty::Shrink(shrink_by)),
+            "halve" => Ok(Penalty::Halve),
+            _ => Err(format!("unknown penalty '{}'", name)),
+        }
+    }
+
+    /// The length of the snake after paying the penalty.
+    pub fn apply(self, length: usize) -> usize {
+        let length = match self {
+            Penalty::Reset => INITIAL_LENGTH,
+            Penalty::Shrink(segments) => length.saturating_sub(segments),
+            Penalty::Halve => length / 2,
+        };
+        length.max(INITIAL_LENGTH)
+    }
+}
+
+/// What happens when the snake reaches the border.
+#[derive(Clone, Copy, PartialEq, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub enum Walls {
+    /// The border is a wall, running into it is a collision.
+    #[default]
+    Solid,
+    /// Leaving the board on one side enters it again on the other side.
+    Wrap,
+}
+
+impl FromStr for Walls {
+    type Err = String;
+
+    fn from_str(name: &str) -> std::result::Result<Walls, String> {
+        match name {
+            "solid" => Ok(Walls::Solid),
+            "wrap" => Ok(Walls::Wrap),
+            _ => Err(format!("unknown walls '{}'", name)),
+        }
+    }
+}
+
+/// The chance per move of the snake that each power-up shows up.
+#[derive(Clone, Copy, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
+pub struct PowerUpRates {
+    pub bomb: f64,
+    pub line: f64,
+    pub magnet: f64,
+}
+
+impl Default for PowerUpRates {
+    fn default() -> Self {
+        PowerUpRates {
+            bomb: 0.003,
+            line: 0.002,
+            magnet: 0.002,
+        }
+    }
+}
+
+enum GameOverChoice {
+    Restart,
+    Continue,
+    Quit,
+}
+
+/// Settings that only apply to the current session and are not saved with the game.
+pub struct RunOptions {
+    pub target_fps: f64,
+    /// The height of a board cell divided by its width, which is two terminal columns.
+    pub cell_aspect: f64,
+    /// How often the board is drawn, independent of how often the snake moves.
+    pub render_fps: f64,
+    pub show_fps: bool,
+    /// Draws two board rows into one terminal row, see `ScreenBuffer::draw_half_blocks`.
+    pub half_block: bool,
+    pub screenshot_format: Format,
+    pub theme: Theme,
+}
+
+/// What a game that is played right now keeps between the moves, but doesn't save.
+pub struct Session {
+    player: Player,
+    /// A short message shown instead of the status line, and until when.
+    notice: Option<(String, Instant)>,
+    /// The speed factor of the last speed or slow food, and until when it lasts.
+    effect: Option<(f64, Instant)>,
+    magnet_moves: usize,
+    adaptive: AdaptiveSpeed,
+    /// The keys pressed since the last move, each move takes at most one turn.
+    turns: VecDeque<KeyEvent>,
+}
+
+impl Session {
+    /// Turns the snake like the arrow key does, with the next move that hasn't turned yet.
+    ///
+    /// A turn only checks the direction of the move before it, so two turns between two moves
+    /// would let the snake reverse into its own body.
+    pub fn press(&mut self, key: KeyEvent) {
+        if self.turns.len() < MAX_QUEUED_TURNS {
+            self.turns.push_back(key);
+        }
+    }
+
+    pub fn snake(&self) -> &Snake {
+        &self.player.snake
+    }
+}
+
+/// The collision that ended the game, with the snake that would play on.
+pub struct GameOver {
+    pub length: usize,
+    respawned: Snake,
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct SnakeGame {
     reveal: bool,
     is_new: bool,
     score: usize,
     screen_buffer: ScreenBuffer,
+    #[serde(default = "default_message")]
+    message: Message,
+    #[serde(default = "default_locale")]
+    locale: String,
+    #[serde(default)]
+    recipient: Option<String>,
+    #[serde(default)]
+    sender: Option<String>,
+    #[serde(default)]
+    total_food: usize,
+    #[serde(default)]
+    mode: Mode,
+    #[serde(default)]
+    lives: usize,
+    #[serde(default)]
+    max_lives: usize,
+    #[serde(default)]
+    play_time: Duration,
+    #[serde(default)]
+    penalty: Penalty,
+    #[serde(default)]
+    walls: Walls,
+    #[serde(default)]
+    level: Option<Level>,
+    #[serde(default)]
+    high_score: usize,
+    #[serde(default)]
+    power_ups: PowerUpRates,
+    #[serde(default)]
+    difficulty: Difficulty,
+    #[serde(skip, default = "default_special_food_chance")]
+    special_food_chance: f64,
+    #[serde(skip, default = "default_rng")]
+    rng: StdRng,
 }
 
 impl SnakeGame {
-    pub fn new(reveal: bool, screen_buffer: ScreenBuffer) -> SnakeGame {
+    pub fn new(reveal: bool, screen_buffer: ScreenBuffer, message: Message) -> SnakeGame {
         SnakeGame {
             reveal,
             is_new: true,
             score: 0,
             screen_buffer,
+            message,
+            locale: default_locale(),
+            recipient: None,
+            sender: None,
+            total_food: 0,
+            mode: Mode::Forgiving,
+            lives: 0,
+            max_lives: 0,
+            play_time: Duration::default(),
+            penalty: Penalty::Reset,
+            walls: Walls::Solid,
+            level: None,
+            high_score: 0,
+            power_ups: PowerUpRates::default(),
+            difficulty: Difficulty::Normal,
+            special_food_chance: SPECIAL_FOOD_CHANCE,
+            rng: default_rng(),
+        }
+    }
+
+    pub fn score(&self) -> usize {
+        self.score
+    }
+
+    /// Makes the food, the power-ups and the new snakes show up the same way every time.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    /// Takes the penalty and the power-up rates from the preset, the speed is up to the caller.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+        self.penalty = difficulty.penalty();
+        self.power_ups = difficulty.power_ups();
+    }
+
+    /// Plays on the walls and food of the level, the screen buffer has to have its size.
+    pub fn set_level(&mut self, level: Level) {
+        self.level = Some(level);
+    }
+
+    fn initial_snake(&self) -> Snake {
+        self.level
+            .as_ref()
+            .and_then(Level::spawn)
+            .unwrap_or_else(Snake::new)
+    }
+
+    pub fn set_walls(&mut self, walls: Walls) {
+        self.walls = walls;
+    }
+
+    pub fn set_text_layout(&mut self, text_layout: TextLayout) {
+        self.screen_buffer.set_text_layout(text_layout);
+    }
+
+    pub fn power_up_rates(&self) -> PowerUpRates {
+        self.power_ups
+    }
+
+    pub fn set_power_up_rates(&mut self, power_ups: PowerUpRates) {
+        self.power_ups = power_ups;
+    }
+
+    /// Zero keeps the special food off the board, so that only the plain food is left to eat.
+    #[cfg(test)]
+    pub fn set_special_food_chance(&mut self, chance: f64) {
+        self.special_food_chance = chance;
+    }
+
+    pub fn set_penalty(&mut self, penalty: Penalty) {
+        self.penalty = penalty;
+    }
+
+    pub fn set_mode(&mut self, mode: Mode, lives: usize) {
+        self.mode = mode;
+        self.max_lives = lives;
+        self.lives = lives;
+    }
+
+    pub fn high_score(&self) -> usize {
+        self.high_score
+    }
+
+    pub fn set_high_score(&mut self, high_score: usize) {
+        self.high_score = high_score;
+    }
+
+    /// Starts the game over with a fresh board, keeping the message and the settings.
+    fn restart(&mut self) {
+        self.reset_board();
+        // the border is drawn over the board, so it doesn't count as food
+        self.add_border();
+        self.total_food = self.screen_buffer.count_food();
+        self.score = 0;
+        self.lives = self.max_lives;
+        self.play_time = Duration::default();
+    }
+
+    /// Puts the walls of the level and the food on the board, without the border.
+    fn reset_board(&mut self) {
+        // the classic mode spawns its food one at a time while playing
+        let with_food = !self.reveal && self.mode != Mode::Classic;
+        match &self.level {
+            Some(level) => level.apply(&mut self.screen_buffer, with_food),
+            None => self.screen_buffer.set_all(if with_food {
+                GameContent::Food
+            } else {
+                GameContent::Empty
+            }),
+        }
+    }
+
+    /// The text of the message in the current locale.
+    pub fn text(&self) -> &str {
+        self.message.select(&self.locale)
+    }
+
+    /// The board as it was left, with the message drawn on the cells that are revealed so far.
+    ///
+    /// Fails if the message doesn't fit the board with the text layout of the game.
+    pub fn board(&self) -> io::Result<ScreenBuffer> {
+        let mut board = self.screen_buffer.clone();
+        if self.is_new {
+            // without the border, so that only the message and the walls take up rows
+            let mut game = self.clone();
+            game.reset_board();
+            board = game.screen_buffer;
+        }
+        board.clear_characters();
+        board.layout_text(self.text(), PADDING)?;
+        board.fill_with_text(self.text().to_string(), PADDING);
+        Ok(board)
+    }
+
+    /// The board the way it is shown while playing, without the snake.
+    pub fn screen(&self) -> io::Result<ScreenBuffer> {
+        let mut game = self.clone();
+        game.screen_buffer = self.board()?;
+        game.draw_overlay(self.text(), None);
+        Ok(game.screen_buffer)
+    }
+
+    fn add_border(&mut self) {
+        match self.walls {
+            Walls::Solid => self.screen_buffer.add_border(GameContent::Border),
+            // a dashed border shows that it can be passed
+            Walls::Wrap => self
+                .screen_buffer
+                .add_dashed_border(GameContent::Border, GameContent::Empty),
+        }
+    }
+
+    /// Draws the border with the score and the status line, and the message on top of the board.
+    fn draw_overlay(&mut self, text: &str, notice: Option<&str>) {
+        let ui = i18n::ui_strings(&self.locale);
+        let screen_height = self.screen_buffer.height();
+        self.add_border();
+        if let Some(level) = &self.level {
+            level.draw_portals(&mut self.screen_buffer);
+        }
+        let header = match self.mode {
+            Mode::Forgiving => format!("{} {}", ui.score, self.score),
+            Mode::Survival => format!("{} {}  {} {}", ui.score, self.score, ui.lives, self.lives),
+            Mode::Classic => format!(
+                "{} {}  {} {}",
+                ui.score,
+                self.score,
+                ui.high_score,
+                self.high_score.max(self.score)
+            ),
+        };
+        self.screen_buffer.set_centered_text_at_row(0, &header);
+        if let Some(notice) = notice {
+            self.screen_buffer
+                .set_centered_text_at_row(screen_height - 1, notice);
+        } else if self.total_food > 0 {
+            let revealed = self.total_food - self.screen_buffer.count_food();
+            self.screen_buffer.set_centered_text_at_row(
+                screen_height - 1,
+                &format!(
+                    "{} {}% ({}/{})",
+                    ui.revealed,
+                    revealed * 100 / self.total_food,
+                    revealed,
+                    self.total_food
+                ),
+            );
+        }
+        if self.mode != Mode::Classic {
+            self.screen_buffer.fill_with_text(text.to_string(), PADDING);
+        }
+    }
+
+    /// Puts the content on a random cell that is empty or shows the message, if there is one.
+    fn spawn_at_random(&mut self, content: GameContent) {
+        let (height, width) = (self.screen_buffer.height(), self.screen_buffer.width());
+        let free: Vec<Coordinate> = (1..height - 1)
+            .flat_map(|row| (1..width - 1).map(move |col| Coordinate { row, col }))
+            .filter(|cell| {
+                matches!(
+                    self.screen_buffer.get_at(cell.row, cell.col),
+                    GameContent::Empty | GameContent::Character(_)
+                )
+            })
+            .collect();
+        if free.is_empty() {
+            return;
+        }
+        let cell = free[self.rng.gen_range(0, free.len())];
+        self.screen_buffer.set_at(cell.row, cell.col, content);
+    }
+
+    /// Now and then puts a random special food on the board.
+    fn spawn_special_food(&mut self) {
+        if !self.rng.gen_bool(self.special_food_chance) {
+            return;
+        }
+        let (height, width) = (self.screen_buffer.height(), self.screen_buffer.width());
+        let special = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.screen_buffer.get_at(row, col).is_special_food())
+            .count();
+        if special >= MAX_SPECIAL_FOOD {
+            return;
+        }
+        // golden food is the most common, it helps to reveal the message
+        let content = match self.rng.gen_range(0, 5) {
+            0 | 1 => GameContent::GoldenFood,
+            2 => GameContent::Poison,
+            3 => GameContent::SpeedFood,
+            _ => GameContent::SlowFood,
+        };
+        self.spawn_at_random(content);
+    }
+
+    /// Rolls for each power-up whether it shows up with this move.
+    fn spawn_power_ups(&mut self) {
+        let rates = [
+            (GameContent::Bomb, self.power_ups.bomb),
+            (GameContent::Line, self.power_ups.line),
+            (GameContent::Magnet, self.power_ups.magnet),
+        ];
+        for &(power_up, rate) in rates.iter() {
+            if rate <= 0.0 || !self.rng.gen_bool(rate.min(1.0)) {
+                continue;
+            }
+            let (height, width) = (self.screen_buffer.height(), self.screen_buffer.width());
+            let power_ups = (0..height)
+                .flat_map(|row| (0..width).map(move |col| (row, col)))
+                .filter(|&(row, col)| self.screen_buffer.get_at(row, col).is_power_up())
+                .count();
+            if power_ups < MAX_POWER_UPS {
+                self.spawn_at_random(power_up);
+            }
+        }
+    }
+
+    /// Eats the food in the row or the column the snake moves along and returns how much there was.
+    fn reveal_line(&mut self, head: Coordinate, direction: Direction) -> usize {
+        let cells: Vec<Coordinate> = match direction {
+            Direction::LEFT | Direction::RIGHT => (0..self.screen_buffer.width())
+                .map(|col| Coordinate { row: head.row, col })
+                .collect(),
+            Direction::UP | Direction::DOWN => (0..self.screen_buffer.height())
+                .map(|row| Coordinate { row, col: head.col })
+                .collect(),
+        };
+        let mut revealed = 0;
+        for cell in cells {
+            if self.screen_buffer.get_at(cell.row, cell.col).is_food() {
+                self.screen_buffer
+                    .set_at(cell.row, cell.col, GameContent::Empty);
+                revealed += 1;
+            }
+        }
+        revealed
+    }
+
+    /// Eats the food within the radius around the cell and returns how much there was.
+    fn reveal_around(&mut self, center: Coordinate, radius: usize) -> usize {
+        let (height, width) = (self.screen_buffer.height(), self.screen_buffer.width());
+        let mut revealed = 0;
+        for row in center.row.saturating_sub(radius)..(center.row + radius + 1).min(height) {
+            for col in center.col.saturating_sub(radius)..(center.col + radius + 1).min(width) {
+                if self.screen_buffer.get_at(row, col).is_food() {
+                    self.screen_buffer.set_at(row, col, GameContent::Empty);
+                    revealed += 1;
+                }
+            }
+        }
+        revealed
+    }
+
+    /// How many times faster than the target speed the snake moves.
+    fn speed_up(&self) -> f64 {
+        let speed_up = match self.mode {
+            Mode::Classic => 1.0 + CLASSIC_SPEED_UP * self.score as f64,
+            _ => {
+                let (step, every) = self.difficulty.speed_step();
+                1.0 + step * (self.score / every) as f64
+            }
+        };
+        speed_up.min(MAX_SPEED_UP)
+    }
+
+    /// Whether the whole message has been revealed by eating.
+    pub fn is_complete(&self) -> bool {
+        !self.reveal && self.total_food > 0 && self.screen_buffer.count_food() == 0
+    }
+
+    /// The width and the height of the board.
+    pub fn size(&self) -> (usize, usize) {
+        (self.screen_buffer.width(), self.screen_buffer.height())
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn set_locale(&mut self, locale: String) {
+        self.locale = locale;
+    }
+
+    /// The gift is greeted on the start screen in the language of the locale.
+    pub fn set_recipient(&mut self, recipient: String, sender: Option<String>) {
+        self.recipient = Some(recipient);
+        self.sender = sender;
+    }
+
+    /// Gets the board ready to play on and starts a session with the first snake.
+    ///
+    /// A new game starts over, a loaded one forgets the text of the last session, which may have
+    /// been in another language.
+    pub fn begin(&mut self) -> Session {
+        if self.is_new {
+            self.restart();
+            self.is_new = false;
+        } else {
+            self.screen_buffer.clear_characters();
+            if self.total_food == 0 && !self.reveal {
+                // saves from older versions don't know how much food there was
+                self.total_food = self.screen_buffer.count_food() + self.score;
+            }
         }
+        let snake = self.initial_snake();
+        // drawn right away, so that the first frame already shows where the snake starts
+        add_snake_to_buffer(&mut self.screen_buffer, &snake.body_pos);
+        Session {
+            player: Player {
+                snake,
+                ..Player::new(
+                    KeyEvent::from(KeyCode::Left),
+                    KeyEvent::from(KeyCode::Right),
+                    KeyEvent::from(KeyCode::Up),
+                    KeyEvent::from(KeyCode::Down),
+                )
+            },
+            notice: None,
+            effect: None,
+            magnet_moves: 0,
+            adaptive: AdaptiveSpeed::new(),
+            turns: VecDeque::new(),
+        }
+    }
+
+    /// How many times faster than the target speed the snake moves right now.
+    fn speed(&self, session: &mut Session, now: Instant) -> f64 {
+        session.effect = session.effect.filter(|&(_, until)| now < until);
+        self.speed_up()
+            * session.effect.map_or(1.0, |(factor, _)| factor)
+            * session.adaptive.factor()
     }
 
-    pub fn run(&mut self, target_fps: f64) -> Result<()> {
+    /// Moves the snake by one cell, eats what it finds there and handles a collision.
+    ///
+    /// A collision that ends the game is returned instead of placing the new snake, the caller
+    /// decides how to go on.
+    pub fn step(&mut self, session: &mut Session, now: Instant) -> Option<GameOver> {
+        let ui = i18n::ui_strings(&self.locale);
+        let screen_width = self.screen_buffer.width();
+        let screen_height = self.screen_buffer.height();
+        if let Some(key) = session.turns.pop_front() {
+            session.player.update_snake_direction(key, true);
+        }
+        let snake = &mut session.player.snake;
+        let removed_tail = move_snake(
+            &mut snake.body_pos,
+            snake.direction,
+            self.walls,
+            screen_width,
+            screen_height,
+        );
+        self.screen_buffer
+            .set_at(removed_tail.row, removed_tail.col, GameContent::Empty);
+
+        if let Some(exit) = self
+            .level
+            .as_ref()
+            .and_then(|level| level.portal_exit(snake.body_pos[0]))
+        {
+            snake.body_pos[0] = exit;
+        }
+
+        let head = snake.body_pos[0];
+        let hit_wall = matches!(
+            self.screen_buffer.get_at(head.row, head.col),
+            GameContent::Wall
+        );
+        match self.screen_buffer.get_at(head.row, head.col) {
+            GameContent::Food => {
+                self.score += 1;
+
+                // grow snake
+                snake.body_pos.push(*snake.body_pos.last().unwrap());
+            }
+            GameContent::GoldenFood => {
+                let points = GOLDEN_POINTS + self.reveal_around(head, GOLDEN_RADIUS);
+                self.score += points;
+                snake.body_pos.push(*snake.body_pos.last().unwrap());
+                session.notice = Some((
+                    format!("{} +{}", ui.golden_food, points),
+                    now + NOTICE_DURATION,
+                ));
+            }
+            GameContent::Poison => {
+                let length = snake.body_pos.len();
+                let keep = length.saturating_sub(POISON_SHRINK).max(INITIAL_LENGTH);
+                let removed = snake.body_pos.split_off(keep);
+                remove_snake_from_buffer(&mut self.screen_buffer, &removed);
+                session.notice = Some((
+                    format!("{} -{}", ui.poison, length - keep),
+                    now + NOTICE_DURATION,
+                ));
+            }
+            GameContent::SpeedFood => {
+                session.effect = Some((SPEED_EFFECT, now + EFFECT_DURATION));
+                let message = format!("{} {}s", ui.faster, EFFECT_DURATION.as_secs());
+                session.notice = Some((message, now + NOTICE_DURATION));
+            }
+            GameContent::SlowFood => {
+                session.effect = Some((SLOW_EFFECT, now + EFFECT_DURATION));
+                let message = format!("{} {}s", ui.slower, EFFECT_DURATION.as_secs());
+                session.notice = Some((message, now + NOTICE_DURATION));
+            }
+            GameContent::Bomb => {
+                let revealed = self.reveal_around(head, BOMB_RADIUS);
+                self.score += revealed;
+                session.notice =
+                    Some((format!("{} +{}", ui.bomb, revealed), now + NOTICE_DURATION));
+            }
+            GameContent::Line => {
+                let revealed = self.reveal_line(head, snake.direction);
+                self.score += revealed;
+                session.notice =
+                    Some((format!("{} +{}", ui.line, revealed), now + NOTICE_DURATION));
+            }
+            GameContent::Magnet => {
+                session.magnet_moves = MAGNET_MOVES;
+                session.notice = Some((
+                    format!("{} {}", ui.magnet, MAGNET_MOVES),
+                    now + NOTICE_DURATION,
+                ));
+            }
+            _ => {}
+        }
+        if session.magnet_moves > 0 {
+            session.magnet_moves -= 1;
+            self.score += self.reveal_around(head, 1);
+        }
+
+        // check for snake border, wall and snake ego collisions
+        let collided = hit_wall
+            || check_border_and_ego_collision(
+                &snake.body_pos,
+                self.walls,
+                screen_width,
+                screen_height,
+            );
+        if self.difficulty == Difficulty::Adaptive {
+            session.adaptive.on_move(collided);
+        }
+        if collided {
+            let length = snake.body_pos.len();
+            let spawn_near = match self.penalty {
+                Penalty::Reset => Coordinate {
+                    row: self.rng.gen_range(1, screen_height - 1),
+                    col: self.rng.gen_range(1, screen_width - 1),
+                },
+                Penalty::Shrink(_) | Penalty::Halve => snake.body_pos[0],
+            };
+            // look for a free spot before the old body is removed, so that it isn't used
+            let new_length = self.penalty.apply(length);
+            let respawned = Snake::find_spawn(&self.screen_buffer, spawn_near, new_length)
+                .unwrap_or_else(|| {
+                    // without a free spot the snake still keeps the length the penalty left it
+                    let mut snake = Snake::new_random(screen_height, screen_width, &mut self.rng);
+                    let tail = *snake.body_pos.last().unwrap();
+                    snake.body_pos.resize(new_length, tail);
+                    snake
+                });
+            remove_snake_from_buffer(&mut self.screen_buffer, &snake.body_pos);
+            let is_game_over = match self.mode {
+                Mode::Forgiving => false,
+                Mode::Survival => {
+                    self.lives = self.lives.saturating_sub(1);
+                    self.lives == 0
+                }
+                Mode::Classic => true,
+            };
+            if is_game_over {
+                return Some(GameOver { length, respawned });
+            }
+            *snake = respawned;
+        }
+
+        self.place_snake(snake);
+        None
+    }
+
+    /// Draws the snake on the board, which is drawn with the next frame, and lets new food and,
+    /// unless the mode is classic, special food and power-ups show up.
+    fn place_snake(&mut self, snake: &Snake) {
+        add_snake_to_buffer(&mut self.screen_buffer, &snake.body_pos);
+        if self.mode == Mode::Classic {
+            // the classic rules only know the plain food, one at a time
+            if self.screen_buffer.count_food() == 0 {
+                self.spawn_at_random(GameContent::Food);
+            }
+        } else {
+            self.spawn_special_food();
+            self.spawn_power_ups();
+        }
+    }
+
+    /// The board with the border, the status line and the message, ready to be drawn.
+    pub fn frame(&mut self, session: &mut Session, now: Instant) -> &ScreenBuffer {
+        session.notice = session.notice.take().filter(|&(_, until)| now < until);
+        let text = self.text().to_string();
+        self.draw_overlay(
+            &text,
+            session.notice.as_ref().map(|(notice, _)| notice.as_str()),
+        );
+        &self.screen_buffer
+    }
+
+    pub fn run(&mut self, renderer: &mut dyn Renderer, options: &RunOptions) -> Result<()> {
         let event_queue = KeyEventQueue::new();
         let thread_event_queue = event_queue.clone();
 
         // launch seperate thread to deal with keyboard input
         thread::spawn(move || send_events(&thread_event_queue));
 
-        let mut stdout = stdout();
-        enable_raw_mode()?;
-        stdout.execute(cursor::Hide)?;
-
-        stdout.execute(terminal::Clear(terminal::ClearType::All))?;
+        let screen_height = self.screen_buffer.height();
+        let ui = i18n::ui_strings(&self.locale);
+        let text = self.message.select(&self.locale).to_string();
+        if self.mode != Mode::Classic {
+            // the message is drawn with every frame, so it has to fit before the terminal is
+            // taken over
+            self.screen_buffer.layout_text(&text, PADDING)?;
+        }
 
-        let screen_width = self.screen_buffer.height();
-        let screen_height = self.screen_buffer.width();
+        renderer.start()?;
 
         if self.is_new {
             // clear screen
             self.screen_buffer.set_all(GameContent::Empty);
             self.screen_buffer
-                .set_centered_text_at_row(screen_height / 2 - 6, "SNAKE");
+                .set_centered_text_at_row(screen_height / 2 - 6, ui.title);
             self.screen_buffer
-                .set_centered_text_at_row(screen_height / 2 - 4, "ESC to stop");
+                .set_centered_text_at_row(screen_height / 2 - 4, ui.esc_to_stop);
+            if let Some(recipient) = &self.recipient {
+                let greeting = match &self.sender {
+                    Some(sender) => {
+                        format!("{} {} {} {}", ui.gift_for, recipient, ui.gift_from, sender)
+                    }
+                    None => format!("{} {}", ui.gift_for, recipient),
+                };
+                self.screen_buffer
+                    .set_centered_text_at_row(screen_height / 2 - 2, &greeting);
+            }
             self.screen_buffer
-                .set_centered_text_at_row(screen_height / 2 + 2, "~ CONTROLS IT by ARROWS ~");
+                .set_centered_text_at_row(screen_height / 2 + 2, ui.controls);
 
             for n in (0..5).rev() {
-                self.screen_buffer
-                    .set_centered_text_at_row(screen_height - 2, &format!("Starting in {}", n));
-                self.screen_buffer.draw(&mut stdout)?;
+                self.screen_buffer.set_centered_text_at_row(
+                    screen_height - 2,
+                    &format!("{} {}", ui.starting_in, n),
+                );
+                draw(&self.screen_buffer, renderer, options)?;
                 thread::sleep(Duration::from_secs(1));
             }
-
-            self.screen_buffer.set_all(GameContent::Empty);
-
-            if self.reveal {
-                self.screen_buffer.set_all(GameContent::Empty);
-            } else {
-                self.screen_buffer.set_all(GameContent::Food);
-            }
-            self.is_new = false;
         }
+        let mut session = self.begin();
 
-        let mut player = Player::new(
-            KeyEvent::from(KeyCode::Left),
-            KeyEvent::from(KeyCode::Right),
-            KeyEvent::from(KeyCode::Up),
-            KeyEvent::from(KeyCode::Down),
-        );
+        let mut session_start = Instant::now();
+        let mut time_before_session = self.play_time;
 
-        // 0: up, 1: right, 2: down, 3: left
-        let mut game_loop_begin = std::time::SystemTime::now();
-        let mut game_loop_end = std::time::SystemTime::now();
-        let horizontal_target_cycle_time = Duration::from_secs_f64(1.0 / target_fps);
-        'outer: loop {
-            // ensure constant cycle time of game loop (i.e. constant snake speed)
-            let game_loop_runtime = game_loop_end.duration_since(game_loop_begin).unwrap();
-            let target_cycle_time = horizontal_target_cycle_time;
+        let horizontal_target_cycle_time = Duration::from_secs_f64(1.0 / options.target_fps);
+        // a cell is taller than it is wide, so a vertical move takes longer to cover the same distance
+        let vertical_target_cycle_time = horizontal_target_cycle_time.mul_f64(options.cell_aspect);
+        let render_cycle_time = Duration::from_secs_f64(1.0 / options.render_fps);
+        let terminal_height = if options.half_block {
+            self.screen_buffer.half_block_height()
+        } else {
+            screen_height
+        };
+        let mut frame_stats = FrameStats::new();
 
-            if game_loop_runtime < target_cycle_time {
-                thread::sleep(target_cycle_time - game_loop_runtime);
-            }
+        // a fixed timestep: the time that passed is used up in whole moves, independent of how
+        // often the board is drawn, so a slow frame doesn't slow the snake down
+        let mut previous = Instant::now();
+        let mut accumulator = Duration::default();
+        let mut step_time;
+        let mut next_render = previous;
+        'outer: loop {
+            let now = Instant::now();
+            accumulator += now - previous;
+            previous = now;
 
-            game_loop_begin = std::time::SystemTime::now();
             if let Some(events) = event_queue.get_all_events() {
                 if !events.is_empty() {
                     if !find_matches(
@@ -122,6 +913,19 @@ impl SnakeGame {
                         break 'outer;
                     }
 
+                    if !find_matches(&events, &[KeyEvent::from(KeyCode::Char('p'))]).is_empty() {
+                        let message = match export::save_screenshot(
+                            &self.screen_buffer,
+                            options.screenshot_format,
+                            &options.theme,
+                        ) {
+                            Ok(path) => format!("saved {}", path),
+                            Err(err) => format!("can't save: {}", err),
+                        };
+                        session.notice = Some((message, now + NOTICE_DURATION));
+                    }
+
+                    let player = &session.player;
                     let event_matches = find_matches(
                         &events,
                         &[
@@ -133,78 +937,208 @@ impl SnakeGame {
                     );
 
                     if !event_matches.is_empty() {
-                        player.update_snake_direction(*event_matches.last().unwrap(), true);
+                        session.press(*event_matches.last().unwrap());
                     }
                 }
             }
 
-            let removed_tail = move_snake(&mut player.snake.body_pos, player.snake.direction);
-            self.screen_buffer
-                .set_at(removed_tail.row, removed_tail.col, GameContent::Empty);
+            loop {
+                step_time = match session.player.snake.direction {
+                    Direction::LEFT | Direction::RIGHT => horizontal_target_cycle_time,
+                    Direction::UP | Direction::DOWN => vertical_target_cycle_time,
+                }
+                .div_f64(self.speed(&mut session, now));
+                // after a long hiccup the snake skips the moves instead of racing through them
+                accumulator = accumulator.min(step_time * MAX_CATCH_UP_MOVES);
+                if accumulator < step_time {
+                    break;
+                }
+                accumulator -= step_time;
 
-            let head = player.snake.body_pos[0];
-            if let GameContent::Food = self.screen_buffer.get_at(head.row, head.col) {
-                self.score += 1;
+                if let Some(game_over) = self.step(&mut session, now) {
+                    self.play_time = time_before_session + session_start.elapsed();
+                    self.high_score = self.high_score.max(self.score);
+                    let mut snake = game_over.respawned;
+                    match self.game_over(renderer, &event_queue, game_over.length, options)? {
+                        GameOverChoice::Restart => {
+                            self.restart();
+                            snake = self.initial_snake();
+                            session_start = Instant::now();
+                            time_before_session = Duration::default();
+                        }
+                        GameOverChoice::Continue => self.mode = Mode::Forgiving,
+                        GameOverChoice::Quit => {
+                            // the next session starts a new game
+                            self.restart();
+                            self.is_new = true;
+                            session.player.snake.body_pos = vec![];
+                            break 'outer;
+                        }
+                    }
+                    // the time on the game over screen doesn't count for the moves
+                    previous = Instant::now();
+                    accumulator = Duration::default();
+                    self.place_snake(&snake);
+                    session.player.snake = snake;
+                }
+            }
 
-                // grow snake
-                player
-                    .snake
-                    .body_pos
-                    .push(*player.snake.body_pos.last().unwrap());
+            if now >= next_render {
+                draw(self.frame(&mut session, now), renderer, options)?;
+                if options.show_fps {
+                    frame_stats.record(now);
+                    renderer.print_line(terminal_height as u16, &frame_stats.summary())?;
+                    renderer.flush()?;
+                }
+                next_render = (next_render + render_cycle_time).max(now);
             }
 
-            // check for snake border and snake ego collisions
-            if check_border_and_ego_collision(&player.snake.body_pos, screen_width, screen_height) {
-                player.snake.body_pos.into_iter().for_each(|coordinate| {
-                    self.screen_buffer
-                        .set_at(coordinate.row, coordinate.col, GameContent::Empty);
-                });
-                player.snake = Snake::new_random(screen_height, screen_width);
+            if self.is_complete() {
+                break 'outer;
             }
 
-            // clear, update and draw screen buffer
-            add_snake_to_buffer(&mut self.screen_buffer, &player.snake.body_pos);
+            // sleep until the next move or the next frame, whichever comes first
+            let until_step = step_time.saturating_sub(accumulator + previous.elapsed());
+            let until_render = next_render.saturating_duration_since(Instant::now());
+            thread::sleep(until_step.min(until_render));
+        }
+        remove_snake_from_buffer(&mut self.screen_buffer, &session.player.snake.body_pos);
+        self.high_score = self.high_score.max(self.score);
+        if !self.is_new {
+            self.play_time = time_before_session + session_start.elapsed();
+        }
 
-            self.screen_buffer.add_border(GameContent::Border);
+        if self.is_complete() {
+            // celebrate with the whole message on a clean board
+            self.add_border();
+            self.screen_buffer
+                .set_centered_text_at_row(0, &format!("* {} *", ui.message_complete));
             self.screen_buffer
-                .set_centered_text_at_row(0, &format!("Score: {}", self.score));
-            self.screen_buffer.fill_with_text(TEXT.to_string(), PADDING);
-            self.screen_buffer.draw(&mut stdout)?;
+                .set_centered_text_at_row(screen_height - 1, ui.press_any_key);
+            self.screen_buffer.fill_with_text(text, PADDING);
+            draw(&self.screen_buffer, renderer, options)?;
+            while event_queue.get_all_events().unwrap_or_default().is_empty() {
+                thread::sleep(Duration::from_millis(50));
+            }
+        }
+
+        renderer.finish()
+    }
+
+    fn game_over(
+        &self,
+        renderer: &mut dyn Renderer,
+        event_queue: &KeyEventQueue<KeyEvent>,
+        length: usize,
+        options: &RunOptions,
+    ) -> Result<GameOverChoice> {
+        let ui = i18n::ui_strings(&self.locale);
+        let screen_height = self.screen_buffer.height();
+        let seconds = self.play_time.as_secs();
+
+        let mut screen = ScreenBuffer::new(
+            self.screen_buffer.width(),
+            screen_height,
+            GameContent::Empty,
+        );
+        screen.add_border(GameContent::Border);
+        // the rows are spread out like the text, so that they stay apart when drawn with half blocks
+        let spacing = self.screen_buffer.line_spacing() as isize;
+        let row = |offset: isize| (screen_height as isize / 2 + offset * spacing) as usize;
+        screen.set_centered_text_at_row(row(-6), ui.game_over);
+        screen.set_centered_text_at_row(row(-3), &format!("{} {}", ui.score, self.score));
+        screen.set_centered_text_at_row(row(-2), &format!("{} {}", ui.length, length));
+        screen.set_centered_text_at_row(
+            row(-1),
+            &format!("{} {}:{:02}", ui.time, seconds / 60, seconds % 60),
+        );
+        // there is no message to continue for in the classic mode, but a high score to beat
+        let can_continue = self.mode != Mode::Classic;
+        if !can_continue {
+            screen.set_centered_text_at_row(
+                row(0),
+                &format!("{} {}", ui.high_score, self.high_score),
+            );
+        }
+        screen.set_centered_text_at_row(row(2), ui.restart);
+        if can_continue {
+            screen.set_centered_text_at_row(row(3), ui.continue_forgiving);
+        }
+        screen.set_centered_text_at_row(row(4), ui.quit);
+        draw(&screen, renderer, options)?;
 
-            game_loop_end = std::time::SystemTime::now();
+        loop {
+            for event in event_queue.get_all_events().unwrap_or_default() {
+                match event.code {
+                    KeyCode::Char('r') => return Ok(GameOverChoice::Restart),
+                    KeyCode::Char('c') if can_continue => return Ok(GameOverChoice::Continue),
+                    KeyCode::Esc | KeyCode::Char('q') => return Ok(GameOverChoice::Quit),
+                    _ => {}
+                }
+            }
+            thread::sleep(Duration::from_millis(50));
         }
-        player.snake.body_pos.into_iter().for_each(|coordinate| {
-            self.screen_buffer
-                .set_at(coordinate.row, coordinate.col, GameContent::Empty);
-        });
+    }
+}
 
-        stdout.execute(cursor::Show)?;
-        disable_raw_mode()
+fn draw(buffer: &ScreenBuffer, renderer: &mut dyn Renderer, options: &RunOptions) -> Result<()> {
+    if options.half_block {
+        buffer.draw_half_blocks(renderer, &options.theme)
+    } else {
+        buffer.draw(renderer, &options.theme)
     }
 }
 
-pub fn move_snake(snake: &mut Vec<Coordinate>, snake_direction: Direction) -> Coordinate {
+/// Moves the head one cell in the given direction and drops the tail, which is returned.
+///
+/// With solid walls the head stops at the border, where the collision is detected. With wrapping
+/// walls it enters the board again on the other side of the border.
+pub fn move_snake(
+    snake: &mut Vec<Coordinate>,
+    snake_direction: Direction,
+    walls: Walls,
+    screen_width: usize,
+    screen_height: usize,
+) -> Coordinate {
+    let head = snake[0];
+    let (last_row, last_col) = (screen_height - 2, screen_width - 2);
     // add head in new direction
-    let new_head = match snake_direction {
-        Direction::UP => Coordinate {
+    let new_head = match (snake_direction, walls) {
+        (Direction::UP, Walls::Wrap) if head.row <= 1 => Coordinate {
+            row: last_row,
+            col: head.col,
+        },
+        (Direction::RIGHT, Walls::Wrap) if head.col >= last_col => Coordinate {
+            row: head.row,
+            col: 1,
+        },
+        (Direction::DOWN, Walls::Wrap) if head.row >= last_row => Coordinate {
+            row: 1,
+            col: head.col,
+        },
+        (Direction::LEFT, Walls::Wrap) if head.col <= 1 => Coordinate {
+            row: head.row,
+            col: last_col,
+        },
+        (Direction::UP, _) => Coordinate {
             // up
-            row: snake[0].row - 1,
-            col: snake[0].col,
+            row: head.row.saturating_sub(1),
+            col: head.col,
         },
-        Direction::RIGHT => Coordinate {
+        (Direction::RIGHT, _) => Coordinate {
             // right
-            row: snake[0].row,
-            col: snake[0].col + 1,
+            row: head.row,
+            col: (head.col + 1).min(screen_width - 1),
         },
-        Direction::DOWN => Coordinate {
+        (Direction::DOWN, _) => Coordinate {
             // down
-            row: snake[0].row + 1,
-            col: snake[0].col,
+            row: (head.row + 1).min(screen_height - 1),
+            col: head.col,
         },
-        Direction::LEFT => Coordinate {
+        (Direction::LEFT, _) => Coordinate {
             // left
-            row: snake[0].row,
-            col: snake[0].col - 1,
+            row: head.row,
+            col: head.col.saturating_sub(1),
         },
     };
 
@@ -220,13 +1154,15 @@ pub fn snake_item_collision(snake: &[Coordinate], item: &Coordinate) -> bool {
 
 pub fn check_border_and_ego_collision(
     snake_body: &[Coordinate],
+    walls: Walls,
     screen_width: usize,
     screen_height: usize,
 ) -> bool {
-    snake_body[0].row == 0
+    let border_collision = snake_body[0].row == 0
         || snake_body[0].row == screen_height - 1
         || snake_body[0].col == 0
-        || snake_body[0].col == screen_width - 1
+        || snake_body[0].col == screen_width - 1;
+    (walls == Walls::Solid && border_collision)
         || snake_item_collision(&snake_body[1..], &snake_body[0])
 }
 
@@ -262,8 +1198,35 @@ impl Snake {
         }
     }
 
-    pub fn new_random(height: usize, width: usize) -> Snake {
-        let mut rng = rand::thread_rng();
+    /// A snake of the initial length with its head at `head`, moving in `direction`.
+    pub fn new_at(head: Coordinate, direction: Direction) -> Snake {
+        let body_pos = (0..INITIAL_LENGTH)
+            .map(|i| match direction {
+                Direction::UP => Coordinate {
+                    row: head.row + i,
+                    col: head.col,
+                },
+                Direction::RIGHT => Coordinate {
+                    row: head.row,
+                    col: head.col.wrapping_sub(i),
+                },
+                Direction::DOWN => Coordinate {
+                    row: head.row.wrapping_sub(i),
+                    col: head.col,
+                },
+                Direction::LEFT => Coordinate {
+                    row: head.row,
+                    col: head.col + i,
+                },
+            })
+            .collect();
+        Snake {
+            body_pos,
+            direction,
+        }
+    }
+
+    pub fn new_random(height: usize, width: usize, rng: &mut impl Rng) -> Snake {
         let row = rng.gen_range(1, height - 4);
         let col = rng.gen_range(1, width - 1);
         let snake_body = vec![
@@ -276,8 +1239,69 @@ impl Snake {
             direction: Direction::UP,
         }
     }
+
+    /// Finds the free spot closest to `near` for a snake of the given length.
+    ///
+    /// The head and the two segments behind it have to be on empty cells, so the snake never
+    /// lands on food, text or another body, and the cell in front of the head has to be free too.
+    /// The remaining segments are stacked on the tail and unroll while the snake moves.
+    pub fn find_spawn(buffer: &ScreenBuffer, near: Coordinate, length: usize) -> Option<Snake> {
+        let height = buffer.height() as isize;
+        let width = buffer.width() as isize;
+        let is_free = |row: isize, col: isize| {
+            row > 0
+                && col > 0
+                && row < height - 1
+                && col < width - 1
+                && matches!(
+                    buffer.get_at(row as usize, col as usize),
+                    GameContent::Empty
+                )
+        };
+        let directions = [
+            (Direction::UP, -1, 0),
+            (Direction::RIGHT, 0, 1),
+            (Direction::DOWN, 1, 0),
+            (Direction::LEFT, 0, -1),
+        ];
+
+        let (near_row, near_col) = (near.row as isize, near.col as isize);
+        for distance in 0..height.max(width) {
+            for row in near_row - distance..=near_row + distance {
+                for col in near_col - distance..=near_col + distance {
+                    let on_ring =
+                        (row - near_row).abs() == distance || (col - near_col).abs() == distance;
+                    if !on_ring {
+                        continue;
+                    }
+                    for &(direction, d_row, d_col) in directions.iter() {
+                        let cells: Vec<(isize, isize)> = (-1..INITIAL_LENGTH as isize)
+                            .map(|i| (row - i * d_row, col - i * d_col))
+                            .collect();
+                        if cells.iter().all(|&(row, col)| is_free(row, col)) {
+                            let mut body_pos: Vec<Coordinate> = cells[1..]
+                                .iter()
+                                .map(|&(row, col)| Coordinate {
+                                    row: row as usize,
+                                    col: col as usize,
+                                })
+                                .collect();
+                            let tail = *body_pos.last().unwrap();
+                            body_pos.resize(length.max(INITIAL_LENGTH), tail);
+                            return Some(Snake {
+                                body_pos,
+                                direction,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Direction {
     UP,
@@ -347,7 +1371,7 @@ impl Player {
     }
 
     fn _update_direction_two_keys(&mut self, key_event: crossterm::event::KeyEvent) {
-        let directions_ordered = vec![
+        let directions_ordered = [
             Direction::UP,
             Direction::RIGHT,
             Direction::DOWN,
@@ -373,6 +1397,19 @@ impl Player {
     }
 }
 
+/// Empties the cells the snake was drawn on, leaving anything it collided with in place.
+pub fn remove_snake_from_buffer(screen_buffer: &mut ScreenBuffer, snake: &[Coordinate]) {
+    for coord in snake {
+        if let GameContent::SnakeHead
+        | GameContent::SnakeHeadWithChar(_)
+        | GameContent::SnakeBody
+        | GameContent::SnakeBodyWithChar(_) = screen_buffer.get_at(coord.row, coord.col)
+        {
+            screen_buffer.set_at(coord.row, coord.col, GameContent::Empty);
+        }
+    }
+}
+
 pub fn add_snake_to_buffer(screen_buffer: &mut ScreenBuffer, snake: &[Coordinate]) {
     screen_buffer.set_at(snake[0].row, snake[0].col, GameContent::SnakeHead);
 
@@ -388,3 +1425,91 @@ pub fn add_snake_to_buffer(screen_buffer: &mut ScreenBuffer, snake: &[Coordinate
         screen_buffer.set_at(coord.row, coord.col, GameContent::SnakeBody);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn direction() -> impl Strategy<Value = Direction> {
+        prop_oneof![
+            Just(Direction::UP),
+            Just(Direction::DOWN),
+            Just(Direction::LEFT),
+            Just(Direction::RIGHT),
+        ]
+    }
+
+    fn walls() -> impl Strategy<Value = Walls> {
+        prop_oneof![Just(Walls::Solid), Just(Walls::Wrap)]
+    }
+
+    /// A board of at least 3x3 cells and a cell inside its border.
+    fn board_and_inner_cell() -> impl Strategy<Value = (usize, usize, Coordinate)> {
+        (3..30usize, 3..30usize).prop_flat_map(|(width, height)| {
+            (1..height - 1, 1..width - 1)
+                .prop_map(move |(row, col)| (width, height, Coordinate { row, col }))
+        })
+    }
+
+    fn is_on_border(cell: Coordinate, width: usize, height: usize) -> bool {
+        cell.row == 0 || cell.col == 0 || cell.row == height - 1 || cell.col == width - 1
+    }
+
+    proptest! {
+        #[test]
+        fn the_snake_leaves_the_inside_only_with_a_collision(
+            (width, height, head) in board_and_inner_cell(),
+            direction in direction(),
+            walls in walls(),
+            length in 1..6usize,
+        ) {
+            let mut body = vec![head; length];
+            let removed_tail = move_snake(&mut body, direction, walls, width, height);
+            prop_assert_eq!(removed_tail, head);
+            prop_assert_eq!(body.len(), length);
+            let head = body[0];
+            prop_assert!(head.row < height && head.col < width);
+            if is_on_border(head, width, height) {
+                prop_assert_eq!(walls, Walls::Solid);
+                prop_assert!(check_border_and_ego_collision(&body, walls, width, height));
+            }
+        }
+
+        #[test]
+        fn moving_from_the_border_doesnt_overflow(
+            width in 3..30usize,
+            height in 3..30usize,
+            on_row in any::<bool>(),
+            far_side in any::<bool>(),
+            offset in 0..30usize,
+            direction in direction(),
+        ) {
+            let head = match (on_row, far_side) {
+                (true, false) => Coordinate { row: 0, col: offset % width },
+                (true, true) => Coordinate { row: height - 1, col: offset % width },
+                (false, false) => Coordinate { row: offset % height, col: 0 },
+                (false, true) => Coordinate { row: offset % height, col: width - 1 },
+            };
+            let mut body = vec![head, head];
+            move_snake(&mut body, direction, Walls::Solid, width, height);
+            prop_assert!(body[0].row < height && body[0].col < width);
+            if is_on_border(body[0], width, height) {
+                prop_assert!(check_border_and_ego_collision(&body, Walls::Solid, width, height));
+            }
+        }
+
+        #[test]
+        fn wrapping_keeps_the_snake_inside(
+            (width, height, head) in board_and_inner_cell(),
+            directions in prop::collection::vec(direction(), 1..60),
+        ) {
+            let mut body = vec![head; 3];
+            for direction in directions {
+                move_snake(&mut body, direction, Walls::Wrap, width, height);
+                prop_assert!(!is_on_border(body[0], width, height));
+                prop_assert_eq!(body.len(), 3);
+            }
+        }
+    }
+}
@@ -0,0 +1,524 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::{env, fs, io};
+
+use crossterm::style::Color;
+
+use crate::export::{rgb, NAMED_COLORS};
+use crate::screen_buffer::GameContent;
+
+pub const THEMES_FILE: &str = "themes.json";
+const BUILTIN: [&str; 6] = [
+    "classic",
+    "dark",
+    "solarized",
+    "high-contrast",
+    "colorblind",
+    "colorblind-dark",
+];
+
+/// How many colors the terminal can show.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+impl FromStr for ColorSupport {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<ColorSupport, String> {
+        match name {
+            "truecolor" | "24bit" => Ok(ColorSupport::TrueColor),
+            "256" => Ok(ColorSupport::Ansi256),
+            "16" => Ok(ColorSupport::Ansi16),
+            _ => Err(format!("unknown color support '{}'", name)),
+        }
+    }
+}
+
+impl ColorSupport {
+    /// Guesses the color support from `$COLORTERM` and `$TERM`, the way most terminal programs do.
+    pub fn detect() -> ColorSupport {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+
+    /// The closest color the terminal can show.
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, _) => color,
+            (ColorSupport::Ansi256, Color::Rgb { r, g, b }) => Color::AnsiValue(ansi_256(r, g, b)),
+            (ColorSupport::Ansi16, Color::Rgb { .. })
+            | (ColorSupport::Ansi16, Color::AnsiValue(_)) => ansi_16(rgb(color)),
+            _ => color,
+        }
+    }
+}
+
+/// How the cells of the board are told apart.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Shapes {
+    /// Every cell is a block of its color.
+    Blocks,
+    /// The snake, the food, the walls and the specials are drawn as their own glyph in their color,
+    /// so they can be told apart without seeing the colors.
+    Glyphs,
+    /// Plain ASCII characters without any colors, for terminals that can't show either.
+    Ascii,
+}
+
+/// Whether the terminal asks for no colors with `$NO_COLOR`, or can't show them with `TERM=dumb`.
+pub fn is_dumb_terminal() -> bool {
+    env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty())
+        || env::var("TERM").is_ok_and(|term| term == "dumb")
+}
+
+/// The colors the board is drawn with.
+///
+/// The blocks of each kind of cell are drawn in its color, the characters of the message in
+/// `text` on `empty`, on the snake in `snake_text` and on the border in `border_text`. With
+/// `Shapes::Glyphs` the glyphs are drawn in the color of their cell on `empty`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub snake_head: Color,
+    pub snake_body: Color,
+    pub food: Color,
+    pub border: Color,
+    pub empty: Color,
+    pub wall: Color,
+    pub portal: Color,
+    pub golden_food: Color,
+    pub poison: Color,
+    pub speed_food: Color,
+    pub slow_food: Color,
+    pub bomb: Color,
+    pub line: Color,
+    pub magnet: Color,
+    pub text: Color,
+    pub snake_text: Color,
+    pub border_text: Color,
+    pub shapes: Shapes,
+}
+
+impl Theme {
+    /// The 16 colors the game always had, made for light terminals.
+    pub fn classic() -> Theme {
+        Theme {
+            snake_head: Color::DarkGreen,
+            snake_body: Color::Green,
+            food: Color::Black,
+            border: Color::DarkBlue,
+            empty: Color::White,
+            wall: Color::DarkGrey,
+            portal: Color::Magenta,
+            golden_food: Color::Yellow,
+            poison: Color::Red,
+            speed_food: Color::Cyan,
+            slow_food: Color::Blue,
+            bomb: Color::DarkRed,
+            line: Color::DarkYellow,
+            magnet: Color::DarkMagenta,
+            text: Color::Black,
+            snake_text: Color::Black,
+            border_text: Color::White,
+            shapes: Shapes::Blocks,
+        }
+    }
+
+    /// A dark board with light food, for dark terminals.
+    pub fn dark() -> Theme {
+        Theme {
+            snake_head: hex(0x2e8b57),
+            snake_body: hex(0x3cb371),
+            food: hex(0x9e9e9e),
+            border: hex(0x3a5fcd),
+            empty: hex(0x1c1c1c),
+            wall: hex(0x5a5a5a),
+            portal: hex(0xda70d6),
+            golden_food: hex(0xffd700),
+            poison: hex(0xe0433a),
+            speed_food: hex(0x40c8e0),
+            slow_food: hex(0x6a8cff),
+            bomb: hex(0xb22222),
+            line: hex(0xc8a000),
+            magnet: hex(0x9932cc),
+            text: hex(0xe8e8e8),
+            snake_text: hex(0x101010),
+            border_text: hex(0xffffff),
+            shapes: Shapes::Blocks,
+        }
+    }
+
+    /// The colors of the Solarized dark palette.
+    pub fn solarized() -> Theme {
+        Theme {
+            snake_head: hex(0x859900),
+            snake_body: hex(0xa4b82a),
+            food: hex(0x586e75),
+            border: hex(0x268bd2),
+            empty: hex(0x002b36),
+            wall: hex(0x073642),
+            portal: hex(0xd33682),
+            golden_food: hex(0xb58900),
+            poison: hex(0xdc322f),
+            speed_food: hex(0x2aa198),
+            slow_food: hex(0x6c71c4),
+            bomb: hex(0xcb4b16),
+            line: hex(0x93a1a1),
+            magnet: hex(0xeee8d5),
+            text: hex(0xfdf6e3),
+            snake_text: hex(0x002b36),
+            border_text: hex(0xfdf6e3),
+            shapes: Shapes::Blocks,
+        }
+    }
+
+    /// Pure colors far apart from each other.
+    pub fn high_contrast() -> Theme {
+        Theme {
+            snake_head: hex(0x00a000),
+            snake_body: hex(0x00ff00),
+            food: hex(0x000000),
+            border: hex(0x0000ff),
+            empty: hex(0xffffff),
+            wall: hex(0x808080),
+            portal: hex(0xff00ff),
+            golden_food: hex(0xffd700),
+            poison: hex(0xff0000),
+            speed_food: hex(0x00ffff),
+            slow_food: hex(0x0060ff),
+            bomb: hex(0x800000),
+            line: hex(0xff8000),
+            magnet: hex(0x8000ff),
+            text: hex(0x000000),
+            snake_text: hex(0x000000),
+            border_text: hex(0xffffff),
+            shapes: Shapes::Blocks,
+        }
+    }
+
+    /// The Okabe-Ito colors, which stay apart with every kind of color blindness, on a light board.
+    /// The head and the body differ in brightness too.
+    pub fn colorblind() -> Theme {
+        Theme {
+            snake_head: hex(0xd55e00),
+            snake_body: hex(0x0072b2),
+            food: hex(0x000000),
+            border: hex(0x000000),
+            empty: hex(0xffffff),
+            wall: hex(0x999999),
+            portal: hex(0xcc79a7),
+            golden_food: hex(0xf0e442),
+            poison: hex(0x882255),
+            speed_food: hex(0x56b4e9),
+            slow_food: hex(0x332288),
+            bomb: hex(0xe69f00),
+            line: hex(0x009e73),
+            magnet: hex(0xaa4499),
+            text: hex(0x000000),
+            snake_text: hex(0xffffff),
+            border_text: hex(0xffffff),
+            shapes: Shapes::Blocks,
+        }
+    }
+
+    /// The Okabe-Ito colors on a dark board.
+    pub fn colorblind_dark() -> Theme {
+        Theme {
+            snake_head: hex(0xe69f00),
+            snake_body: hex(0x56b4e9),
+            food: hex(0xbbbbbb),
+            border: hex(0x0072b2),
+            empty: hex(0x1c1c1c),
+            wall: hex(0x555555),
+            portal: hex(0xcc79a7),
+            golden_food: hex(0xf0e442),
+            poison: hex(0xd55e00),
+            speed_food: hex(0x009e73),
+            slow_food: hex(0x332288),
+            bomb: hex(0x882255),
+            line: hex(0x44aa99),
+            magnet: hex(0xaa4499),
+            text: hex(0xf0f0f0),
+            snake_text: hex(0x000000),
+            border_text: hex(0xffffff),
+            shapes: Shapes::Blocks,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Theme> {
+        match name {
+            "classic" => Some(Theme::classic()),
+            "dark" => Some(Theme::dark()),
+            "solarized" => Some(Theme::solarized()),
+            "high-contrast" => Some(Theme::high_contrast()),
+            "colorblind" => Some(Theme::colorblind()),
+            "colorblind-dark" => Some(Theme::colorblind_dark()),
+            _ => None,
+        }
+    }
+
+    /// Loads the theme by its name, from the themes file if it has one with that name, or else one
+    /// of the built-in themes.
+    ///
+    /// A theme in the file names the colors it changes and the theme it starts from:
+    /// `{"mine": {"base": "dark", "food": "#404040", "empty": "black"}}`.
+    pub fn load(name: &str, path: Option<&str>) -> io::Result<Theme> {
+        let file = match fs::read_to_string(path.unwrap_or(THEMES_FILE)) {
+            Ok(file) => Some(file),
+            // the default themes file is optional, but one that was asked for has to be there
+            Err(err) if path.is_none() && err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        let mut themes: BTreeMap<String, BTreeMap<String, String>> = match file {
+            Some(file) => serde_json::from_str(&file)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            None => BTreeMap::new(),
+        };
+
+        match themes.remove(name) {
+            Some(colors) => Theme::from_config(name, colors),
+            None => Theme::builtin(name).ok_or_else(|| {
+                let mut names: Vec<_> = BUILTIN.iter().map(|name| name.to_string()).collect();
+                names.extend(themes.into_keys());
+                invalid(format!(
+                    "unknown theme '{}', the themes are {}",
+                    name,
+                    names.join(", ")
+                ))
+            }),
+        }
+    }
+
+    fn from_config(name: &str, mut colors: BTreeMap<String, String>) -> io::Result<Theme> {
+        let base = colors
+            .remove("base")
+            .unwrap_or_else(|| "classic".to_string());
+        let mut theme = Theme::builtin(&base)
+            .ok_or_else(|| invalid(format!("theme '{}': unknown base '{}'", name, base)))?;
+        for (key, value) in colors {
+            let color = parse_color(&value)
+                .ok_or_else(|| invalid(format!("theme '{}': bad color '{}'", name, value)))?;
+            let field = match key.as_str() {
+                "snake_head" => &mut theme.snake_head,
+                "snake_body" => &mut theme.snake_body,
+                "food" => &mut theme.food,
+                "border" => &mut theme.border,
+                "empty" => &mut theme.empty,
+                "wall" => &mut theme.wall,
+                "portal" => &mut theme.portal,
+                "golden_food" => &mut theme.golden_food,
+                "poison" => &mut theme.poison,
+                "speed_food" => &mut theme.speed_food,
+                "slow_food" => &mut theme.slow_food,
+                "bomb" => &mut theme.bomb,
+                "line" => &mut theme.line,
+                "magnet" => &mut theme.magnet,
+                "text" => &mut theme.text,
+                "snake_text" => &mut theme.snake_text,
+                "border_text" => &mut theme.border_text,
+                _ => {
+                    return Err(invalid(format!(
+                        "theme '{}': unknown color '{}'",
+                        name, key
+                    )))
+                }
+            };
+            *field = color;
+        }
+        Ok(theme)
+    }
+
+    /// The same theme with every color replaced by the closest one the terminal can show.
+    pub fn adapted(&self, support: ColorSupport) -> Theme {
+        let adapt = |color| support.adapt(color);
+        Theme {
+            snake_head: adapt(self.snake_head),
+            snake_body: adapt(self.snake_body),
+            food: adapt(self.food),
+            border: adapt(self.border),
+            empty: adapt(self.empty),
+            wall: adapt(self.wall),
+            portal: adapt(self.portal),
+            golden_food: adapt(self.golden_food),
+            poison: adapt(self.poison),
+            speed_food: adapt(self.speed_food),
+            slow_food: adapt(self.slow_food),
+            bomb: adapt(self.bomb),
+            line: adapt(self.line),
+            magnet: adapt(self.magnet),
+            text: adapt(self.text),
+            snake_text: adapt(self.snake_text),
+            border_text: adapt(self.border_text),
+            shapes: self.shapes,
+        }
+    }
+
+    /// The color of the blocks of a cell.
+    pub fn color(&self, content: &GameContent) -> Color {
+        match content {
+            GameContent::SnakeHead | GameContent::SnakeHeadWithChar(_) => self.snake_head,
+            GameContent::SnakeBody | GameContent::SnakeBodyWithChar(_) => self.snake_body,
+            GameContent::Food => self.food,
+            GameContent::Border | GameContent::BorderWithChar(_) => self.border,
+            GameContent::Empty | GameContent::Character(_) => self.empty,
+            GameContent::Wall => self.wall,
+            GameContent::Portal => self.portal,
+            GameContent::GoldenFood => self.golden_food,
+            GameContent::Poison => self.poison,
+            GameContent::SpeedFood => self.speed_food,
+            GameContent::SlowFood => self.slow_food,
+            GameContent::Bomb => self.bomb,
+            GameContent::Line => self.line,
+            GameContent::Magnet => self.magnet,
+        }
+    }
+
+    /// The color of the character drawn on a cell.
+    pub fn text_color(&self, content: &GameContent) -> Color {
+        match content {
+            GameContent::SnakeHeadWithChar(_) | GameContent::SnakeBodyWithChar(_) => {
+                self.snake_text
+            }
+            GameContent::BorderWithChar(_) => self.border_text,
+            _ => self.text,
+        }
+    }
+
+    /// The glyph of a cell with `Shapes::Glyphs` or `Shapes::Ascii`.
+    ///
+    /// The border and the empty cells stay blocks with glyphs. The ASCII glyphs aren't letters,
+    /// so they can't be mistaken for the message, and with both the snake hides the message below
+    /// it, so that its head and body never look alike.
+    pub fn glyph(&self, content: &GameContent) -> Option<char> {
+        match self.shapes {
+            Shapes::Blocks => None,
+            Shapes::Glyphs => match content {
+                GameContent::SnakeHead | GameContent::SnakeHeadWithChar(_) => Some('@'),
+                GameContent::SnakeBody | GameContent::SnakeBodyWithChar(_) => Some('o'),
+                // \u{00b7} is a middle dot
+                GameContent::Food => Some('\u{00b7}'),
+                GameContent::Wall => Some('#'),
+                GameContent::Portal => Some('%'),
+                GameContent::GoldenFood => Some('$'),
+                GameContent::Poison => Some('!'),
+                GameContent::SpeedFood => Some('+'),
+                GameContent::SlowFood => Some('-'),
+                GameContent::Bomb => Some('*'),
+                GameContent::Line => Some('='),
+                GameContent::Magnet => Some('&'),
+                _ => None,
+            },
+            Shapes::Ascii => match content {
+                GameContent::SnakeHead | GameContent::SnakeHeadWithChar(_) => Some('@'),
+                GameContent::SnakeBody | GameContent::SnakeBodyWithChar(_) => Some('='),
+                GameContent::Food => Some('.'),
+                GameContent::Border => Some('+'),
+                GameContent::Empty => Some(' '),
+                GameContent::Wall => Some('#'),
+                GameContent::Portal => Some('%'),
+                GameContent::GoldenFood => Some('$'),
+                GameContent::Poison => Some('!'),
+                GameContent::SpeedFood => Some('>'),
+                GameContent::SlowFood => Some('<'),
+                GameContent::Bomb => Some('*'),
+                GameContent::Line => Some('|'),
+                GameContent::Magnet => Some('&'),
+                GameContent::BorderWithChar(_) | GameContent::Character(_) => None,
+            },
+        }
+    }
+}
+
+fn hex(value: u32) -> Color {
+    Color::Rgb {
+        r: (value >> 16) as u8,
+        g: (value >> 8) as u8,
+        b: value as u8,
+    }
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Parses `#rrggbb`, a 256-color index or a crossterm color name like `dark_green`.
+fn parse_color(value: &str) -> Option<Color> {
+    if let Some(digits) = value.strip_prefix('#') {
+        return match digits.len() {
+            6 => u32::from_str_radix(digits, 16).ok().map(hex),
+            _ => None,
+        };
+    }
+    if let Ok(index) = value.parse::<u8>() {
+        return Some(Color::AnsiValue(index));
+    }
+    Some(match value {
+        "black" => Color::Black,
+        "dark_grey" => Color::DarkGrey,
+        "red" => Color::Red,
+        "dark_red" => Color::DarkRed,
+        "green" => Color::Green,
+        "dark_green" => Color::DarkGreen,
+        "yellow" => Color::Yellow,
+        "dark_yellow" => Color::DarkYellow,
+        "blue" => Color::Blue,
+        "dark_blue" => Color::DarkBlue,
+        "magenta" => Color::Magenta,
+        "dark_magenta" => Color::DarkMagenta,
+        "cyan" => Color::Cyan,
+        "dark_cyan" => Color::DarkCyan,
+        "white" => Color::White,
+        "grey" => Color::Grey,
+        _ => return None,
+    })
+}
+
+/// The closest color of the 6x6x6 cube or the gray ramp of the 256-color palette.
+fn ansi_256(r: u8, g: u8, b: u8) -> u8 {
+    let step = |v: u8| -> u8 {
+        if v < 48 {
+            0
+        } else if v < 115 {
+            1
+        } else {
+            (v - 35) / 40
+        }
+    };
+    let cube = 16 + 36 * step(r) + 6 * step(g) + step(b);
+    let average = (r as u16 + g as u16 + b as u16) / 3;
+    let gray = if average > 238 {
+        231
+    } else {
+        232 + (average.saturating_sub(3) / 10) as u8
+    };
+    let distance = |index: u8| distance(rgb(Color::AnsiValue(index)), (r, g, b));
+    if distance(gray) < distance(cube) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// The closest of the 16 named colors.
+fn ansi_16(color: (u8, u8, u8)) -> Color {
+    NAMED_COLORS
+        .iter()
+        .copied()
+        .min_by_key(|&named| distance(rgb(named), color))
+        .unwrap_or(Color::White)
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}